@@ -0,0 +1,54 @@
+use std::{
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+use tacview_realtime_client::acmi::RealTimeReader;
+
+const CONSUMERS: usize = 16;
+const OBJECTS: usize = 10_000;
+
+/// Time taken to clone every record once for each consumer.
+fn fan_out<T: Clone>(records: &[T]) -> Duration {
+    let start = Instant::now();
+    let mut consumers = vec![Vec::new(); CONSUMERS];
+    for record in records {
+        for consumer in &mut consumers {
+            consumer.push(record.clone());
+        }
+    }
+    start.elapsed()
+}
+
+/// Compares the cost of fanning records out to many consumers, cloning them
+/// deeply with `next` or sharing them with `next_arc`.
+#[tokio::main]
+async fn main() {
+    let mut acmi = String::from("FileType=text/acmi/tacview\nFileVersion=2.2\n");
+    for id in 1..=OBJECTS {
+        writeln!(
+            acmi,
+            "{id:X},T=1|2|3,Name=F-16C-52,Type=Air+FixedWing,Pilot=Viper{},Group=Springfield,Coalition=Allies",
+            id % 8,
+        )
+        .unwrap();
+    }
+
+    let mut reader = RealTimeReader::try_from_reader(acmi.as_bytes())
+        .await
+        .expect("failed to read header");
+    let mut records = Vec::new();
+    while let Some(record) = reader.next().await.expect("failed to read record") {
+        records.push(record);
+    }
+    println!("next: {:?}", fan_out(&records));
+
+    let mut reader = RealTimeReader::try_from_reader(acmi.as_bytes())
+        .await
+        .expect("failed to read header");
+    let mut records = Vec::new();
+    while let Some(record) = reader.next_arc().await.expect("failed to read record") {
+        records.push(record);
+    }
+    println!("next_arc: {:?}", fan_out(&records));
+}
@@ -16,15 +16,15 @@ async fn main() {
     let username = next_arg!();
     let password = args.next().unwrap_or_default();
 
-    let mut reader = tacview_realtime_client::connect((host, port), &username, &password)
+    let reader = tacview_realtime_client::connect((host, port), &username, &password)
         .await
         .expect("failed to connect");
 
-    println!("{:#?}", reader.header);
+    println!("{}", reader.header);
 
-    loop {
-        let record = reader.next().await.expect("failed to read next record");
-
-        println!("{record:?}");
-    }
+    reader
+        .for_each(|record| println!("{record:?}"))
+        .await
+        .expect("failed to read next record");
+    println!("server closed the stream");
 }
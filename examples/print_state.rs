@@ -1,11 +1,8 @@
-use std::{
-    collections::HashMap,
-    mem::{discriminant, Discriminant},
-    str::FromStr,
-};
+use std::{collections::HashMap, str::FromStr};
 
 use tacview_realtime_client::acmi::{
-    record::{global_property::GlobalProperty, object_property::ObjectProperty, Record},
+    record::{global_property::GlobalProperty, PropertyKey, Record},
+    state::ObjectState,
     Header,
 };
 
@@ -13,8 +10,8 @@ use tacview_realtime_client::acmi::{
 struct State {
     #[allow(dead_code)]
     acmi_header: Header,
-    global_properties: HashMap<Discriminant<GlobalProperty>, GlobalProperty>,
-    objects: HashMap<u64, HashMap<Discriminant<ObjectProperty>, ObjectProperty>>,
+    global_properties: HashMap<PropertyKey<GlobalProperty>, GlobalProperty>,
+    objects: HashMap<u64, ObjectState>,
 }
 
 #[tokio::main]
@@ -43,9 +40,7 @@ async fn main() {
         objects: HashMap::new(),
     };
 
-    loop {
-        let record = reader.next().await.expect("failed to read next record");
-
+    while let Some(record) = reader.next().await.expect("failed to read next record") {
         match record {
             Record::Remove(id) => {
                 state.objects.remove(&id);
@@ -60,22 +55,23 @@ async fn main() {
                 for global_property in global_properties {
                     state
                         .global_properties
-                        .insert(discriminant(&global_property), global_property);
+                        .insert(global_property.key(), global_property);
                 }
             }
             Record::Update(id, object_properties) => {
                 let entry = state.objects.entry(id).or_default();
                 for object_property in object_properties {
-                    entry.insert(discriminant(&object_property), object_property);
+                    entry.update(object_property);
                 }
             }
         }
 
         println!("header: {:?}", state.acmi_header);
         println!("global_properties: {:?}", state.global_properties);
-        for (id, properties) in &state.objects {
-            println!("object {}: {:?}", id, properties);
+        for (id, object) in &state.objects {
+            println!("object {id:X}: {object}");
         }
         println!();
     }
+    println!("server closed the stream");
 }
@@ -0,0 +1,40 @@
+use std::str::FromStr;
+
+#[tokio::main]
+async fn main() {
+    let mut args = std::env::args();
+    let cmd = args.next().unwrap();
+    macro_rules! next_arg {
+        () => {
+            args.next().unwrap_or_else(|| {
+                panic!("USAGE: {} <HOSTNAME> <PORT> <USERNAME> [<PASSWORD>]", cmd)
+            })
+        };
+    }
+    let host = next_arg!();
+    let port = u16::from_str(&next_arg!()).expect("bad port");
+    let username = next_arg!();
+    let password = args.next().unwrap_or_default();
+
+    let (header, mut client) =
+        tacview_realtime_client::connect_world((host, port), &username, &password)
+            .await
+            .expect("failed to connect");
+    println!("header: {header:?}");
+
+    while !client.is_closed() {
+        let events = client.poll().await.expect("failed to read next frame");
+        println!("time: {}", client.current_time());
+        for event in events {
+            println!("event: {event:?}");
+        }
+        for (id, object) in client.objects() {
+            println!("object {id:X}: {object}");
+        }
+        if let Some(bullseye) = client.bullseye(None) {
+            println!("bullseye: {bullseye}");
+        }
+        println!();
+    }
+    println!("server closed the stream");
+}
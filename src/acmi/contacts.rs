@@ -0,0 +1,107 @@
+use std::collections::VecDeque;
+
+use tokio::io::AsyncBufRead;
+
+use crate::error::Result;
+
+use super::{record::Record, state::ObjectState, world::World, RealTimeReader};
+
+/// Stream of `(frame_time, id, state)` rows, yielding every object once when
+/// it appears, for a "new contacts" view. Created with
+/// [`RealTimeReader::new_contacts`].
+///
+/// Objects are yielded when their first frame ends, with every property
+/// received during that frame. An object removed and added again later is
+/// yielded again.
+#[derive(Debug)]
+pub struct NewContacts<R> {
+    reader: RealTimeReader<R>,
+    world: World,
+    pending: VecDeque<(f64, u64)>,
+    ended: bool,
+}
+
+impl<R> NewContacts<R>
+where
+    R: AsyncBufRead + Unpin,
+{
+    pub(super) fn new(reader: RealTimeReader<R>) -> Self {
+        Self {
+            reader,
+            world: World::new(),
+            pending: VecDeque::new(),
+            ended: false,
+        }
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub async fn next(&mut self) -> Result<Option<(f64, u64, ObjectState)>> {
+        loop {
+            if let Some((time, id)) = self.pending.pop_front() {
+                if let Some(object) = self.world.object(id) {
+                    return Ok(Some((time, id, object.clone())));
+                }
+                continue;
+            }
+            if self.ended {
+                return Ok(None);
+            }
+
+            let record = self.reader.next().await?;
+            if matches!(record, None | Some(Record::Frame(_))) {
+                self.end_frame();
+            }
+            match record {
+                Some(record) => {
+                    self.world.apply(record);
+                }
+                None => self.ended = true,
+            }
+        }
+    }
+
+    fn end_frame(&mut self) {
+        let time = self.world.time();
+        let mut ids: Vec<_> = self.world.frame_spawns().map(|(id, _)| id).collect();
+        ids.sort_unstable();
+        self.pending.extend(ids.into_iter().map(|id| (time, id)));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_new_contacts() {
+        let acmi = b"FileType=text/acmi/tacview
+FileVersion=2.2
+#0
+A,T=1|2|3
+A,Name=F-16C-52
+#1
+A,T=1|2|4
+B,T=4|5|6
+-A
+#2
+A,T=1|2|5,Name=F-15C
+";
+        let reader = RealTimeReader::try_from_reader(&acmi[..]).await.unwrap();
+        let mut contacts = reader.new_contacts();
+        let mut rows = Vec::new();
+        while let Some((time, id, object)) = contacts.next().await.unwrap() {
+            rows.push((time, id, object.name().map(str::to_string)));
+        }
+        assert_eq!(
+            rows,
+            vec![
+                (0.0, 0xA, Some("F-16C-52".to_string())),
+                (1.0, 0xB, None),
+                (2.0, 0xA, Some("F-15C".to_string())),
+            ]
+        );
+    }
+}
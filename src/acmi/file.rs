@@ -0,0 +1,285 @@
+#[cfg(feature = "file")]
+use std::{
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::Path,
+};
+use std::{io::BufRead, str::FromStr};
+
+use crate::error::{Error, Result};
+
+#[cfg(feature = "file")]
+use super::ZIP_MAGIC;
+use super::{
+    is_end_of_record, parse_file_type, parse_file_version, record::Record, validate_header, Header,
+    HeaderValidator,
+};
+
+/// Opens an ACMI recording, either plaintext (`.txt.acmi`) or zipped
+/// (`.zip.acmi`). The format is detected from the file content rather than
+/// the extension. Zipped recordings are decompressed on the fly.
+#[cfg(feature = "file")]
+pub fn open(path: impl AsRef<Path>) -> Result<FileReader<Box<dyn BufRead>>> {
+    let mut file = File::open(path).map_err(Error::AcmiReaderRead)?;
+
+    let mut magic = [0; 4];
+    let is_zip = match file.read_exact(&mut magic) {
+        Ok(()) => &magic == ZIP_MAGIC,
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => false,
+        Err(e) => return Err(Error::AcmiReaderRead(e)),
+    };
+    file.seek(SeekFrom::Start(0))
+        .map_err(Error::AcmiReaderRead)?;
+
+    let reader: Box<dyn BufRead> = if is_zip {
+        Box::new(BufReader::new(zip_entry(file)?))
+    } else {
+        Box::new(BufReader::new(file))
+    };
+
+    FileReader::try_from_reader(reader)
+}
+
+/// Reader of the data of the first entry of a zip archive, decompressing it
+/// while reading. The sizes claimed by the archive are not trusted, nothing is
+/// preallocated from them.
+#[cfg(feature = "file")]
+fn zip_entry<R>(reader: R) -> Result<Box<dyn Read>>
+where
+    R: Read + Seek + 'static,
+{
+    let mut archive = zip::ZipArchive::new(reader).map_err(Error::Zip)?;
+    let (data_start, compressed_size, compression) = {
+        let entry = archive.by_index(0).map_err(Error::Zip)?;
+        (
+            entry.data_start(),
+            entry.compressed_size(),
+            entry.compression(),
+        )
+    };
+    let mut reader = archive.into_inner();
+    reader
+        .seek(SeekFrom::Start(data_start))
+        .map_err(Error::AcmiReaderRead)?;
+    let data = reader.take(compressed_size);
+    match compression {
+        zip::CompressionMethod::Stored => Ok(Box::new(data)),
+        zip::CompressionMethod::Deflated => Ok(Box::new(flate2::read::DeflateDecoder::new(data))),
+        _ => Err(Error::Zip(zip::result::ZipError::UnsupportedArchive(
+            "unsupported compression method",
+        ))),
+    }
+}
+
+/// Blocking reader of ACMI records, for recordings stored on disk. Records are
+/// parsed lazily, reading the underlying reader only as far as the next
+/// record.
+#[derive(Debug)]
+pub struct FileReader<R> {
+    pub header: Header,
+    reader: R,
+}
+
+impl<R> FileReader<R>
+where
+    R: BufRead,
+{
+    pub fn try_from_reader(reader: R) -> Result<Self> {
+        Self::try_from_reader_with_validator(reader, validate_header)
+    }
+
+    /// Same as [`try_from_reader`](Self::try_from_reader), checking the
+    /// header with a custom validator instead of [`validate_header`].
+    pub fn try_from_reader_with_validator(
+        mut reader: R,
+        validate: HeaderValidator,
+    ) -> Result<Self> {
+        let mut buf = String::new();
+        reader.read_line(&mut buf).map_err(Error::AcmiReaderRead)?;
+        let file_type = parse_file_type(buf)?;
+
+        let mut buf = String::new();
+        reader.read_line(&mut buf).map_err(Error::AcmiReaderRead)?;
+        let file_version = parse_file_version(buf)?;
+
+        validate(&file_type, &file_version)?;
+        Ok(Self {
+            header: Header {
+                file_type,
+                file_version,
+            },
+            reader,
+        })
+    }
+}
+
+impl<R> Iterator for FileReader<R>
+where
+    R: BufRead,
+{
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = String::new();
+        loop {
+            match self.reader.read_line(&mut line) {
+                Ok(0) if line.is_empty() => return None,
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(e) => return Some(Err(Error::AcmiReaderRead(e))),
+            }
+
+            if is_end_of_record(&mut line) {
+                break;
+            }
+        }
+
+        tracing::debug!(line, "parsing ACMI line");
+        Some(Record::from_str(&line))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{BufReader, Cursor, Read};
+    #[cfg(feature = "file")]
+    use std::{
+        io::Write,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    use super::*;
+    #[cfg(feature = "file")]
+    use crate::temp_dir::TempDir;
+
+    #[cfg(feature = "file")]
+    const ACMI: &str = "\u{feff}FileType=text/acmi/tacview
+FileVersion=2.2
+0,ReferenceTime=2011-06-02T05:00:00Z
+// comment
+#0
+A,Name=F-16C-52,T=1|2|3
+#1.5
+-A
+";
+
+    #[cfg(feature = "file")]
+    fn assert_records(reader: FileReader<Box<dyn BufRead>>) {
+        assert_eq!(reader.header.file_type, "text/acmi/tacview");
+        assert_eq!(reader.header.file_version, "2.2");
+        let records: Vec<_> = reader.map(Result::unwrap).collect();
+        assert_eq!(records.len(), 5);
+        assert_eq!(records[1], Record::Frame(0.0));
+        assert_eq!(records[4], Record::Remove(0xA));
+    }
+
+    #[cfg(feature = "file")]
+    #[test]
+    fn test_open() {
+        let dir = TempDir::new("test-open");
+
+        let path = dir.path().join("test.txt.acmi");
+        std::fs::write(&path, ACMI).unwrap();
+        assert_records(open(&path).unwrap());
+
+        let path = dir.path().join("test.zip.acmi");
+        let mut zip = zip::ZipWriter::new(File::create(&path).unwrap());
+        zip.start_file("test.txt.acmi", Default::default()).unwrap();
+        zip.write_all(ACMI.as_bytes()).unwrap();
+        zip.finish().unwrap();
+        assert_records(open(&path).unwrap());
+    }
+
+    #[cfg(feature = "file")]
+    #[test]
+    fn test_untrusted_zip_size() {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file("test.txt.acmi", Default::default()).unwrap();
+        zip.write_all(ACMI.as_bytes()).unwrap();
+        let mut zip = zip.finish().unwrap().into_inner();
+
+        // claim a huge uncompressed size in the central directory
+        let central = zip
+            .windows(4)
+            .position(|window| window == b"PK\x01\x02")
+            .unwrap();
+        zip[central + 24..central + 28].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        let reader: Box<dyn BufRead> =
+            Box::new(BufReader::new(zip_entry(Cursor::new(zip)).unwrap()));
+        assert_records(FileReader::try_from_reader(reader).unwrap());
+    }
+
+    /// Counts the bytes read from the inner reader.
+    #[cfg(feature = "file")]
+    struct CountingReader<R> {
+        inner: R,
+        read: Arc<AtomicUsize>,
+    }
+
+    #[cfg(feature = "file")]
+    impl<R: Read> Read for CountingReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.inner.read(buf)?;
+            self.read.fetch_add(n, Ordering::Relaxed);
+            Ok(n)
+        }
+    }
+
+    #[cfg(feature = "file")]
+    impl<R: Seek> Seek for CountingReader<R> {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[cfg(feature = "file")]
+    #[test]
+    fn test_zip_bounded_memory() {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options =
+            zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        zip.start_file("test.txt.acmi", options).unwrap();
+        zip.write_all(b"FileType=text/acmi/tacview\nFileVersion=2.2\n")
+            .unwrap();
+        for frame in 0..100_000 {
+            writeln!(zip, "#{frame}\nA,T=1|2|3").unwrap();
+        }
+        let zip = zip.finish().unwrap().into_inner();
+        assert!(zip.len() > 1 << 20);
+
+        let read = Arc::new(AtomicUsize::new(0));
+        let entry = zip_entry(CountingReader {
+            inner: Cursor::new(zip),
+            read: read.clone(),
+        })
+        .unwrap();
+        let mut reader = FileReader::try_from_reader(BufReader::new(entry)).unwrap();
+        for _ in 0..10 {
+            reader.next().unwrap().unwrap();
+        }
+        assert!(read.load(Ordering::Relaxed) < 64 << 10);
+        assert_eq!(reader.count(), 200_000 - 10);
+    }
+
+    /// Fails every read, to detect reading past the expected point.
+    struct FailingReader;
+
+    impl Read for FailingReader {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::Other.into())
+        }
+    }
+
+    #[test]
+    fn test_lazy() {
+        let acmi = b"FileType=text/acmi/tacview\nFileVersion=2.2\n#1\n";
+        let reader = BufReader::new(Cursor::new(&acmi[..]).chain(FailingReader));
+        let mut reader = FileReader::try_from_reader(reader).unwrap();
+        assert_eq!(reader.next().unwrap().unwrap(), Record::Frame(1.0));
+        assert!(reader.next().unwrap().is_err());
+    }
+}
@@ -0,0 +1,226 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+use tokio::io::AsyncBufRead;
+
+use crate::error::Result;
+
+use super::{record::Record, RealTimeReader};
+
+/// Bits of a merged object id below the source index, see [`merge`].
+pub const SOURCE_SHIFT: u32 = 56;
+
+/// Id of the object `id` of the `source`-th stream once [merged](merge).
+pub fn merged_id(source: u8, id: u64) -> u64 {
+    u64::from(source) << SOURCE_SHIFT | id
+}
+
+/// Source index and original id of a [merged](merge) object id.
+pub fn split_merged_id(id: u64) -> (u8, u64) {
+    ((id >> SOURCE_SHIFT) as u8, id & ((1 << SOURCE_SHIFT) - 1))
+}
+
+/// Merges two streams into a single one, e.g. from two servers, for a
+/// combined picture.
+///
+/// Object ids are namespaced so they don't collide: the source index, `0` for
+/// `a` and `1` for `b`, is stored in the 8 bits above [`SOURCE_SHIFT`] with
+/// [`merged_id`], including ids in object references and events. Ids of the
+/// sources must fit below these bits. The global id `0` is kept as is.
+///
+/// Frames are interleaved by time, a frame time common to both streams being
+/// sent once. A source is held back at a frame until the other one reaches
+/// it, so a stalled source stalls the merged stream, until it ends. Global
+/// properties pass through, so both sources should share the same reference
+/// longitude and latitude.
+pub fn merge<A, B>(a: RealTimeReader<A>, b: RealTimeReader<B>) -> Merge<A, B>
+where
+    A: AsyncBufRead + Unpin + Send + 'static,
+    B: AsyncBufRead + Unpin + Send + 'static,
+{
+    Merge {
+        a: Source::new(a),
+        b: Source::new(b),
+        time: None,
+    }
+}
+
+type Read<R> =
+    Pin<Box<dyn Future<Output = (Box<RealTimeReader<R>>, Result<Option<Record>>)> + Send>>;
+
+enum State<R> {
+    Idle(Box<RealTimeReader<R>>),
+    Reading(Read<R>),
+    Ended,
+}
+
+struct Source<R> {
+    state: State<R>,
+    /// Frame time read but not sent yet, holding the source back.
+    frame: Option<f64>,
+}
+
+/// What a [`Source`] yielded when polled.
+enum Polled {
+    Record(Result<Record>),
+    Frame,
+    Ended,
+    Pending,
+    Blocked,
+}
+
+impl<R> Source<R>
+where
+    R: AsyncBufRead + Unpin + Send + 'static,
+{
+    fn new(reader: RealTimeReader<R>) -> Self {
+        Self {
+            state: State::Idle(Box::new(reader)),
+            frame: None,
+        }
+    }
+
+    fn poll_read(&mut self, cx: &mut Context<'_>) -> Polled {
+        if self.frame.is_some() {
+            return Polled::Blocked;
+        }
+        let mut read = match std::mem::replace(&mut self.state, State::Ended) {
+            State::Idle(mut reader) => Box::pin(async move {
+                let record = reader.next().await;
+                (reader, record)
+            }),
+            State::Reading(read) => read,
+            State::Ended => return Polled::Ended,
+        };
+        let (reader, record) = match read.as_mut().poll(cx) {
+            Poll::Ready(ready) => ready,
+            Poll::Pending => {
+                self.state = State::Reading(read);
+                return Polled::Pending;
+            }
+        };
+        match record {
+            Ok(None) => Polled::Ended,
+            Ok(Some(Record::Frame(time))) => {
+                self.state = State::Idle(reader);
+                self.frame = Some(time);
+                Polled::Frame
+            }
+            record => {
+                self.state = State::Idle(reader);
+                Polled::Record(record.map(Option::unwrap))
+            }
+        }
+    }
+}
+
+/// Stream returned by [`merge`].
+pub struct Merge<A, B> {
+    a: Source<A>,
+    b: Source<B>,
+    /// Last frame time sent.
+    time: Option<f64>,
+}
+
+impl<A, B> std::fmt::Debug for Merge<A, B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Merge").field("time", &self.time).finish()
+    }
+}
+
+impl<A, B> Stream for Merge<A, B>
+where
+    A: AsyncBufRead + Unpin + Send + 'static,
+    B: AsyncBufRead + Unpin + Send + 'static,
+{
+    type Item = Result<Record>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            let mut pending = false;
+            for source in 0..2 {
+                let polled = match source {
+                    0 => this.a.poll_read(cx),
+                    _ => this.b.poll_read(cx),
+                };
+                match polled {
+                    Polled::Record(record) => {
+                        let record =
+                            record.map(|record| record.map_object_ids(|id| merged_id(source, id)));
+                        return Poll::Ready(Some(record));
+                    }
+                    Polled::Pending => pending = true,
+                    Polled::Frame | Polled::Ended | Polled::Blocked => {}
+                }
+            }
+            if pending {
+                return Poll::Pending;
+            }
+
+            // every source is held back at a frame or has ended
+            let time = match (this.a.frame, this.b.frame) {
+                (Some(a), Some(b)) => a.min(b),
+                (Some(time), None) | (None, Some(time)) => time,
+                (None, None) => return Poll::Ready(None),
+            };
+            for frame in [&mut this.a.frame, &mut this.b.frame] {
+                if *frame == Some(time) {
+                    *frame = None;
+                }
+            }
+            if this.time.replace(time) != Some(time) {
+                return Poll::Ready(Some(Ok(Record::Frame(time))));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use futures_util::StreamExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_merge() {
+        let a = RealTimeReader::try_from_reader(
+            &b"FileType=text/acmi/tacview\nFileVersion=2.2\n#0\nA,T=1|2|3\n#2\n-A\n"[..],
+        )
+        .await
+        .unwrap();
+        let b = RealTimeReader::try_from_reader(
+            &b"FileType=text/acmi/tacview\nFileVersion=2.2\n#0\nA,T=4|5|6,Parent=B\n#1\n0,Event=Destroyed|A|\n#2\n"[..],
+        )
+        .await
+        .unwrap();
+
+        let records: Vec<_> = merge(a, b)
+            .map(|record| record.unwrap().to_string())
+            .collect()
+            .await;
+        assert_eq!(
+            records,
+            vec![
+                "#0",
+                "A,T=1|2|3",
+                "10000000000000A,T=4|5|6,Parent=10000000000000B",
+                "#1",
+                "0,Event=Destroyed|10000000000000A|",
+                "#2",
+                "-A",
+            ]
+        );
+        assert_eq!(split_merged_id(0x10000000000000A), (1, 0xA));
+        assert_eq!(
+            Record::from_str("-10000000000000A").unwrap(),
+            Record::Remove(merged_id(1, 0xA))
+        );
+    }
+}
@@ -1,107 +1,1018 @@
+#[cfg(feature = "net")]
+pub mod contacts;
+pub mod file;
+#[cfg(feature = "net")]
+pub mod merge;
 pub mod record;
+#[cfg(feature = "net")]
+pub mod replay;
+#[cfg(feature = "net")]
+pub mod snapshots;
+pub mod state;
+pub mod summary;
+pub mod window;
+pub mod world;
+pub mod writer;
 
-use std::str::FromStr;
+#[cfg(feature = "net")]
+use std::{collections::HashSet, sync::Arc};
 
 use serde::{Deserialize, Serialize};
-use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+#[cfg(feature = "net")]
+use tokio::{
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, BufReader},
+    sync::mpsc,
+    task::JoinHandle,
+};
 
 use crate::error::{Error, Result};
 
-use self::record::Record;
+pub use self::{summary::summarize, window::parse_window};
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[cfg(feature = "file")]
+pub use self::file::open;
+
+#[cfg(feature = "net")]
+pub use self::merge::merge;
+
+#[cfg(feature = "net")]
+use self::{
+    contacts::NewContacts,
+    record::{Interner, Record, Sanitize},
+    snapshots::ObjectSnapshots,
+};
+
+/// Leading bytes of a zipped recording.
+#[cfg(any(feature = "net", feature = "file"))]
+const ZIP_MAGIC: &[u8; 4] = b"PK\x03\x04";
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Header {
     pub file_type: String,
     pub file_version: String,
 }
 
+/// Short description, e.g. `ACMI text v2.2` for a `text/acmi/tacview` file
+/// of version `2.2`. Other file types are written as is.
+impl std::fmt::Display for Header {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.file_type.as_str() {
+            "text/acmi/tacview" => write!(f, "ACMI text v{}", self.file_version),
+            file_type => write!(f, "{file_type} v{}", self.file_version),
+        }
+    }
+}
+
+/// Reads ACMI records from any byte source, such as `tokio::io::stdin()`.
+#[cfg(feature = "net")]
+pub async fn from_async_read<R>(reader: R) -> Result<RealTimeReader<BufReader<R>>>
+where
+    R: AsyncRead + Unpin,
+{
+    RealTimeReader::try_from_reader(BufReader::new(reader)).await
+}
+
+/// Opens a plaintext ACMI recording (`.txt.acmi`) for async reading. Records
+/// are read lazily, one at a time, so memory stays bounded whatever the size
+/// of the file.
+///
+/// Zipped recordings (`.zip.acmi`) are rejected with an
+/// [`InvalidData`](std::io::ErrorKind::InvalidData) read error rather than
+/// decompressed into memory, see `open` (`file` feature) to read them.
+#[cfg(feature = "net")]
+pub async fn stream_file(
+    path: impl AsRef<std::path::Path>,
+) -> Result<RealTimeReader<BufReader<tokio::fs::File>>> {
+    let file = tokio::fs::File::open(path)
+        .await
+        .map_err(Error::AcmiReaderRead)?;
+    let mut reader = BufReader::new(file);
+    let buf = reader.fill_buf().await.map_err(Error::AcmiReaderRead)?;
+    if buf.starts_with(ZIP_MAGIC) {
+        return Err(Error::AcmiReaderRead(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "zipped recordings cannot be streamed, use acmi::open",
+        )));
+    }
+    RealTimeReader::try_from_reader(reader).await
+}
+
+/// Async reader of ACMI records. [`next`](Self::next) is cancel safe, so it
+/// can be raced in `tokio::select!` without losing data.
+#[cfg(feature = "net")]
 #[derive(Debug)]
 pub struct RealTimeReader<R> {
     pub header: Header,
     reader: R,
+    only_objects: Option<HashSet<u64>>,
+    suppress_empty_frames: bool,
+    sanitize: Option<Sanitize>,
+    ignored_properties: Vec<String>,
+    four_field_heading: bool,
+    reject_duplicates: bool,
+    interner: Interner,
+    time: Option<f64>,
+    /// Frame held back by `suppress_empty_frames` until a record follows.
+    pending_frame: Option<Record>,
+    buffered: Option<Record>,
+    /// Bytes of the line being read, and the lines of the record being
+    /// assembled, kept across cancelled calls to `next`.
+    line: Vec<u8>,
+    record: String,
+    closed: bool,
 }
 
+#[cfg(feature = "net")]
 impl<R> RealTimeReader<R>
 where
     R: AsyncBufRead + Unpin,
 {
-    pub async fn try_from_reader(mut reader: R) -> Result<Self> {
-        let header = parse_header(&mut reader).await?;
-        Ok(Self { header, reader })
+    pub async fn try_from_reader(reader: R) -> Result<Self> {
+        Self::try_from_reader_with_validator(reader, validate_header).await
+    }
+
+    /// Same as [`try_from_reader`](Self::try_from_reader), checking the
+    /// header with a custom validator instead of [`validate_header`].
+    pub async fn try_from_reader_with_validator(
+        mut reader: R,
+        validate: HeaderValidator,
+    ) -> Result<Self> {
+        let header = parse_header(&mut reader, validate).await?;
+        Ok(Self::new(reader, header))
+    }
+
+    /// Creates a reader from a stream whose header has already been read, see
+    /// [`tcp::connect_raw`](crate::tcp::connect_raw).
+    pub fn new(reader: R, header: Header) -> Self {
+        Self {
+            header,
+            reader,
+            only_objects: None,
+            suppress_empty_frames: false,
+            sanitize: None,
+            ignored_properties: Vec::new(),
+            four_field_heading: false,
+            reject_duplicates: false,
+            interner: Interner::new(),
+            time: None,
+            pending_frame: None,
+            buffered: None,
+            line: Vec::new(),
+            record: String::new(),
+            closed: false,
+        }
+    }
+
+    /// Skips `Update` and `Remove` records of objects not in `ids`, before
+    /// their properties are parsed. Frames, events and global properties are
+    /// always read.
+    pub fn only_objects(mut self, ids: HashSet<u64>) -> Self {
+        self.only_objects = Some(ids);
+        self
+    }
+
+    /// Only yields `Frame` records followed by at least one other record
+    /// before the next frame. The time of suppressed frames is still
+    /// available with [`time`](Self::time).
+    pub fn suppress_empty_frames(mut self, suppress_empty_frames: bool) -> Self {
+        self.suppress_empty_frames = suppress_empty_frames;
+        self
+    }
+
+    /// Skips the object properties whose name starts with one of the
+    /// prefixes, e.g. `Radar` or `Fuel`, before they are parsed and stored,
+    /// see [`Record::from_str_ignoring`].
+    pub fn ignore_properties(mut self, prefixes: Vec<String>) -> Self {
+        self.ignored_properties = prefixes;
+        self
+    }
+
+    /// Reads the fourth field of non-standard four-field coordinates
+    /// (`T=Lon|Lat|Alt|Heading`, sent by some 2D exporters) as heading
+    /// instead of roll. Disabled by default.
+    pub fn four_field_heading(mut self, four_field_heading: bool) -> Self {
+        self.four_field_heading = four_field_heading;
+        self
+    }
+
+    /// Fails on updates sending the same property more than once with
+    /// [`Error::DuplicateProperty`], see
+    /// [`Record::from_str_rejecting_duplicates`]. By default every value is
+    /// kept and the last one wins when applied.
+    pub fn reject_duplicates(mut self, reject_duplicates: bool) -> Self {
+        self.reject_duplicates = reject_duplicates;
+        self
+    }
+
+    /// Sanitizes control characters in text values with
+    /// [`Record::sanitize`], logging a warning when it fires. Values are kept
+    /// as received by default.
+    pub fn sanitize(mut self, sanitize: Sanitize) -> Self {
+        self.sanitize = Some(sanitize);
+        self
+    }
+
+    /// Time of the last `Frame` record read, even if suppressed.
+    pub fn time(&self) -> Option<f64> {
+        self.time
+    }
+
+    /// Whether the stream has ended or the reader has been
+    /// [closed](Self::close).
+    pub fn is_closed(&self) -> bool {
+        self.closed
     }
 
-    pub async fn next(&mut self) -> Result<Record> {
-        let mut line = String::new();
+    /// Stops reading. Every following [`next`](Self::next) returns `None`
+    /// without touching the stream.
+    pub fn close(&mut self) {
+        self.closed = true;
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// The underlying stream, e.g. to handle a protocol extension. Reading
+    /// from it between calls to [`next`](Self::next) desyncs parsing unless
+    /// whole lines are consumed.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Reclaims the underlying stream, positioned after the last line read.
+    /// A partially read record, or a frame held back by
+    /// [`suppress_empty_frames`](Self::suppress_empty_frames), is lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads the next record. Returns `None` once the stream has ended, and
+    /// from then on without reading again. A final line without a trailing
+    /// end-of-line is still parsed.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel safe: partially read lines and records are kept
+    /// in the reader, so dropping the future, e.g. in a `tokio::select!`
+    /// branch which did not complete, loses no data and the next call resumes
+    /// where it stopped.
+    pub async fn next(&mut self) -> Result<Option<Record>> {
+        if let Some(record) = self.buffered.take() {
+            return Ok(Some(record));
+        }
+
+        while let Some(record) = self.read_record().await? {
+            if let Record::Frame(time) = record {
+                self.time = Some(time);
+                if self.suppress_empty_frames {
+                    self.pending_frame = Some(record);
+                    continue;
+                }
+            }
+            if let Some(frame) = self.pending_frame.take() {
+                self.buffered = Some(record);
+                return Ok(Some(frame));
+            }
+            return Ok(Some(record));
+        }
+        Ok(None)
+    }
+
+    async fn read_record(&mut self) -> Result<Option<Record>> {
+        if self.closed {
+            return Ok(None);
+        }
+
         loop {
-            self.reader
-                .read_line(&mut line)
+            let read = self
+                .reader
+                .read_until(b'\n', &mut self.line)
                 .await
                 .map_err(Error::AcmiReaderRead)?;
 
-            line = line.strip_suffix('\n').unwrap_or(&line).to_string();
-
-            // comment
-            if line.starts_with("//") {
-                line.clear();
-                continue;
+            // end of stream
+            if read == 0 {
+                self.closed = true;
+                if self.record.is_empty() {
+                    return Ok(None);
+                }
+                break;
             }
 
-            // multiline
-            if line.ends_with('\\') {
-                line.pop();
-                line.push('\n');
-                continue;
+            let line = String::from_utf8(std::mem::take(&mut self.line)).map_err(|error| {
+                Error::AcmiReaderRead(std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+            })?;
+            self.record.push_str(&line);
+            if is_end_of_record(&mut self.record) {
+                let skipped = matches!(
+                    (&self.only_objects, object_id(&self.record)),
+                    (Some(ids), Some(id)) if !ids.contains(&id)
+                );
+                if !skipped {
+                    break;
+                }
+                self.record.clear();
             }
+        }
+        let line = std::mem::take(&mut self.record);
 
-            break;
+        let span = tracing::debug_span!(
+            "record",
+            frame_time = self.time,
+            object_id = tracing::field::Empty,
+            record_kind = tracing::field::Empty,
+        );
+        let _entered = span.enter();
+        if !span.is_disabled() {
+            if let Some(id) = object_id(&line) {
+                span.record("object_id", record::format_object_id(id).as_str());
+            }
         }
 
         tracing::debug!(line, "parsing ACMI line");
-        Record::from_str(&line)
+        match Record::parse(
+            &line,
+            None,
+            &self.ignored_properties,
+            self.four_field_heading,
+            self.reject_duplicates,
+        ) {
+            Ok(mut record) => {
+                span.record("record_kind", tracing::field::debug(record.kind()));
+                if let Some(sanitize) = self.sanitize {
+                    if record.sanitize(sanitize) {
+                        tracing::warn!(line, "sanitized control characters in ACMI line");
+                    }
+                }
+                Ok(Some(record))
+            }
+            Err(error) => {
+                tracing::debug!(line, %error, "failed to parse ACMI line");
+                Err(error)
+            }
+        }
+    }
+
+    /// Calls `f` with every record until the stream ends.
+    pub async fn for_each<F>(mut self, mut f: F) -> Result<()>
+    where
+        F: FnMut(Record),
+    {
+        while let Some(record) = self.next().await? {
+            f(record);
+        }
+        Ok(())
+    }
+
+    /// Calls `f` with every record until the stream ends, or `f` fails.
+    pub async fn try_for_each<F, E>(mut self, mut f: F) -> Result<(), E>
+    where
+        F: FnMut(Record) -> Result<(), E>,
+        E: From<Error>,
+    {
+        while let Some(record) = self.next().await? {
+            f(record)?;
+        }
+        Ok(())
+    }
+
+    /// Spawns a task reading every record into a channel of `capacity`
+    /// records. The task waits while the channel is full, so a slow receiver
+    /// slows the reader down. It ends with the stream, returning the error
+    /// which stopped it if any, or when the receiver is dropped.
+    pub fn spawn_into_channel(
+        mut self,
+        capacity: usize,
+    ) -> (JoinHandle<Result<()>>, mpsc::Receiver<Record>)
+    where
+        R: Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let handle = tokio::spawn(async move {
+            while let Some(record) = self.next().await? {
+                if sender.send(record).await.is_err() {
+                    break;
+                }
+            }
+            Ok(())
+        });
+        (handle, receiver)
+    }
+
+    /// Same as [`next`](Self::next), but wraps the record in an [`Arc`] so it
+    /// can be cheaply cloned when fanning out to many consumers. Names,
+    /// groups and coalitions are [interned](Record::intern), so records of
+    /// many objects share their storage too.
+    pub async fn next_arc(&mut self) -> Result<Option<Arc<Record>>> {
+        let Some(mut record) = self.next().await? else {
+            return Ok(None);
+        };
+        record.intern(&mut self.interner);
+        Ok(Some(Arc::new(record)))
+    }
+
+    /// Turns the reader into a stream of per-object snapshots, see
+    /// [`ObjectSnapshots`].
+    pub fn object_snapshots(self) -> ObjectSnapshots<R> {
+        ObjectSnapshots::new(self)
+    }
+
+    /// Turns the reader into a stream of newly appeared objects, see
+    /// [`NewContacts`].
+    pub fn new_contacts(self) -> NewContacts<R> {
+        NewContacts::new(self)
     }
 }
 
-async fn parse_header<R>(reader: &mut R) -> Result<Header>
+#[cfg(feature = "net")]
+pub(crate) async fn parse_header<R>(reader: &mut R, validate: HeaderValidator) -> Result<Header>
 where
     R: AsyncBufRead + Unpin,
 {
-    let mut buf = String::new();
-
     // file type
-    reader
-        .read_line(&mut buf)
-        .await
-        .map_err(Error::AcmiReaderRead)?;
-    if buf != "FileType=text/acmi/tacview\n" {
-        return Err(Error::BadAcmiFileType(buf));
-    }
-    let file_type = buf
-        .strip_prefix("FileType=")
-        .unwrap()
-        .strip_suffix('\n')
-        .unwrap()
-        .to_string();
-    buf.clear();
+    let mut buf = String::new();
+    read_header_line(reader, &mut buf).await?;
+    let file_type = parse_file_type(buf)?;
 
     // file version
-    reader
-        .read_line(&mut buf)
-        .await
-        .map_err(Error::AcmiReaderRead)?;
-    if !buf.starts_with("FileVersion=2.2") {
-        return Err(Error::BadAcmiFileVersion(buf));
-    }
-    let file_version = buf
-        .strip_prefix("FileVersion=")
-        .unwrap()
-        .strip_suffix('\n')
-        .unwrap()
-        .to_string();
-    buf.clear();
+    let mut buf = String::new();
+    read_header_line(reader, &mut buf).await?;
+    let file_version = parse_file_version(buf)?;
 
+    validate(&file_type, &file_version)?;
     Ok(Header {
         file_type,
         file_version,
     })
 }
+
+/// Reads a line of the header. The stream ending before it, e.g. a server
+/// closing the connection right after the handshake, is a recoverable
+/// [`UnexpectedEof`](std::io::ErrorKind::UnexpectedEof) read error.
+#[cfg(feature = "net")]
+async fn read_header_line<R>(reader: &mut R, buf: &mut String) -> Result<()>
+where
+    R: AsyncBufRead + Unpin,
+{
+    if reader.read_line(buf).await.map_err(Error::AcmiReaderRead)? == 0 {
+        return Err(Error::AcmiReaderRead(
+            std::io::ErrorKind::UnexpectedEof.into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Parses a single record from arbitrary bytes, for fuzzing. Invalid UTF-8
+/// is replaced rather than rejected, so every input reaches the parser. Never
+/// panics, and allocates at most in proportion to the input. The line also
+/// goes through [`Record::from_str_lenient`](record::Record::from_str_lenient),
+/// and a parsed record is formatted back, to exercise those paths too.
+pub fn parse_line_fuzz(data: &[u8]) -> Result<record::Record> {
+    let line = String::from_utf8_lossy(data);
+    let _ = record::Record::from_str_lenient(&line);
+    let record = line.parse::<record::Record>()?;
+    let _ = record.to_string();
+    Ok(record)
+}
+
+/// Validates the `FileType` and `FileVersion` values of a header, returning
+/// [`Error::BadAcmiFileType`] or [`Error::BadAcmiFileVersion`] to reject it.
+pub type HeaderValidator = fn(&str, &str) -> Result<()>;
+
+/// Default [`HeaderValidator`], accepting `text/acmi/tacview` files of version
+/// `2.2` and its revisions.
+pub fn validate_header(file_type: &str, file_version: &str) -> Result<()> {
+    if file_type != "text/acmi/tacview" {
+        return Err(Error::BadAcmiFileType(file_type.to_string()));
+    }
+    if !file_version.starts_with("2.2") {
+        return Err(Error::BadAcmiFileVersion(file_version.to_string()));
+    }
+    Ok(())
+}
+
+fn parse_file_type(buf: String) -> Result<String> {
+    // files written on Windows may start with a byte order mark
+    match buf
+        .trim_start_matches('\u{feff}')
+        .trim_end_matches('\n')
+        .strip_prefix("FileType=")
+    {
+        Some(file_type) => Ok(file_type.to_string()),
+        None => Err(Error::BadAcmiFileType(buf)),
+    }
+}
+
+fn parse_file_version(buf: String) -> Result<String> {
+    match buf.trim_end_matches('\n').strip_prefix("FileVersion=") {
+        Some(file_version) => Ok(file_version.to_string()),
+        None => Err(Error::BadAcmiFileVersion(buf)),
+    }
+}
+
+/// Id of the object an `Update` or `Remove` line is about, without parsing
+/// the rest of the line. `None` for the other records.
+#[cfg(feature = "net")]
+fn object_id(line: &str) -> Option<u64> {
+    let id = match line.strip_prefix('-') {
+        Some(id) => id,
+        None => line.split_once(',')?.0,
+    };
+    match u64::from_str_radix(id, 16) {
+        Ok(0) | Err(_) => None,
+        Ok(id) => Some(id),
+    }
+}
+
+/// Handles a line freshly appended to `line`. Returns `true` when `line` holds
+/// a complete record, `false` when more lines must be read (the line was a
+/// comment, or ends with an escaped end-of-line).
+fn is_end_of_record(line: &mut String) -> bool {
+    if line.ends_with('\n') {
+        line.pop();
+    }
+
+    // comment
+    if line.starts_with("//") {
+        line.clear();
+        return false;
+    }
+
+    // multiline
+    if line.ends_with('\\') {
+        line.pop();
+        line.push('\n');
+        return false;
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "net")]
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::acmi::record::Record;
+    #[cfg(feature = "net")]
+    use crate::{
+        acmi::record::object_property::{Coords, ObjectProperty},
+        capture_subscriber::CaptureSubscriber,
+        temp_dir::TempDir,
+    };
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn test_next_arc() {
+        let acmi = b"FileType=text/acmi/tacview\nFileVersion=2.2\nA,Name=F-16C-52,Type=Air\n";
+        let mut reader = RealTimeReader::try_from_reader(&acmi[..]).await.unwrap();
+        let record = reader.next_arc().await.unwrap().unwrap();
+        assert_eq!(
+            *record,
+            Record::from_str("A,Name=F-16C-52,Type=Air").unwrap()
+        );
+        assert_eq!(Arc::clone(&record), record);
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn test_next_arc_interning() {
+        let acmi = b"FileType=text/acmi/tacview\nFileVersion=2.2\nA,Name=F-16C-52,Coalition=Allies\nB,Name=F-16C-52,Coalition=Allies\n";
+        let mut reader = RealTimeReader::try_from_reader(&acmi[..]).await.unwrap();
+        let a = reader.next_arc().await.unwrap().unwrap();
+        let b = reader.next_arc().await.unwrap().unwrap();
+        assert_eq!(
+            *a,
+            Record::from_str("A,Name=F-16C-52,Coalition=Allies").unwrap()
+        );
+        assert_eq!(
+            *b,
+            Record::from_str("B,Name=F-16C-52,Coalition=Allies").unwrap()
+        );
+
+        let (Record::Update(_, a), Record::Update(_, b)) = (&*a, &*b) else {
+            panic!("expected updates");
+        };
+        for (a, b) in a.iter().zip(b) {
+            match (a, b) {
+                (ObjectProperty::Name(a), ObjectProperty::Name(b))
+                | (ObjectProperty::Coalition(a), ObjectProperty::Coalition(b)) => {
+                    assert!(Arc::ptr_eq(a, b));
+                }
+                _ => panic!("unexpected properties {a:?} {b:?}"),
+            }
+        }
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn test_next_without_trailing_eol() {
+        let acmi = b"FileType=text/acmi/tacview\nFileVersion=2.2\n#1\n-A";
+        let mut reader = RealTimeReader::try_from_reader(&acmi[..]).await.unwrap();
+        assert_eq!(reader.next().await.unwrap(), Some(Record::Frame(1.0)));
+        assert_eq!(reader.next().await.unwrap(), Some(Record::Remove(0xA)));
+        assert_eq!(reader.next().await.unwrap(), None);
+        assert_eq!(reader.next().await.unwrap(), None);
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn test_suppress_empty_frames() {
+        let acmi = b"FileType=text/acmi/tacview\nFileVersion=2.2\n#1\n#2\n#3\nA,T=1|2|3\n#4\n#5";
+        let mut reader = RealTimeReader::try_from_reader(&acmi[..])
+            .await
+            .unwrap()
+            .suppress_empty_frames(true);
+        assert_eq!(reader.next().await.unwrap(), Some(Record::Frame(3.0)));
+        assert_eq!(reader.time(), Some(3.0));
+        assert!(matches!(
+            reader.next().await.unwrap(),
+            Some(Record::Update(0xA, _))
+        ));
+        assert_eq!(reader.next().await.unwrap(), None);
+        assert_eq!(reader.time(), Some(5.0));
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn test_cancel_safety() {
+        use tokio::io::AsyncWriteExt;
+
+        let (client, mut server) = tokio::io::duplex(1024);
+        server
+            .write_all(b"FileType=text/acmi/tacview\nFileVersion=2.2\n#1\nA,Name=a\\\nb")
+            .await
+            .unwrap();
+        let mut reader = RealTimeReader::try_from_reader(BufReader::new(client))
+            .await
+            .unwrap();
+        assert_eq!(reader.next().await.unwrap(), Some(Record::Frame(1.0)));
+
+        // reads the first line of the record and part of the second one
+        tokio::select! {
+            biased;
+            record = reader.next() => panic!("unexpected record: {record:?}"),
+            _ = async {} => {}
+        }
+
+        server.write_all(b",T=1|2|3\n").await.unwrap();
+        assert_eq!(
+            reader.next().await.unwrap(),
+            Some(Record::from_str("A,Name=a\nb,T=1|2|3").unwrap())
+        );
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn test_into_inner() {
+        use tokio::io::AsyncReadExt;
+
+        let acmi = b"FileType=text/acmi/tacview\nFileVersion=2.2\n#1\nraw\x00bytes";
+        let mut reader = RealTimeReader::try_from_reader(&acmi[..]).await.unwrap();
+        assert_eq!(reader.next().await.unwrap(), Some(Record::Frame(1.0)));
+        assert_eq!(reader.get_ref().len(), 9);
+        let mut rest = Vec::new();
+        reader.into_inner().read_to_end(&mut rest).await.unwrap();
+        assert_eq!(rest, b"raw\x00bytes");
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn test_spawn_into_channel() {
+        let acmi = b"FileType=text/acmi/tacview\nFileVersion=2.2\n#0\nA,T=1|2|3\n#1\n";
+        let reader = RealTimeReader::try_from_reader(&acmi[..]).await.unwrap();
+        let (handle, mut receiver) = reader.spawn_into_channel(1);
+        let mut records = Vec::new();
+        while let Some(record) = receiver.recv().await {
+            records.push(record.to_string());
+        }
+        assert_eq!(records, ["#0", "A,T=1|2|3", "#1"]);
+        assert!(handle.await.unwrap().is_ok());
+
+        let acmi = b"FileType=text/acmi/tacview\nFileVersion=2.2\n#0\n#x\n";
+        let reader = RealTimeReader::try_from_reader(&acmi[..]).await.unwrap();
+        let (handle, mut receiver) = reader.spawn_into_channel(1);
+        assert_eq!(receiver.recv().await, Some(Record::Frame(0.0)));
+        assert_eq!(receiver.recv().await, None);
+        assert!(matches!(handle.await.unwrap(), Err(Error::ParseFloat(_))));
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn test_unexpected_line() {
+        let acmi =
+            b"FileType=text/acmi/tacview\nFileVersion=2.2\nTacview.RealTimeTelemetry.0\n#1\n";
+        let mut reader = RealTimeReader::try_from_reader(&acmi[..]).await.unwrap();
+        assert!(matches!(
+            reader.next().await,
+            Err(Error::UnexpectedLine(line)) if line == "Tacview.RealTimeTelemetry.0"
+        ));
+        assert_eq!(reader.next().await.unwrap(), Some(Record::Frame(1.0)));
+    }
+
+    #[test]
+    fn test_header_display() {
+        let mut header = Header {
+            file_type: "text/acmi/tacview".to_string(),
+            file_version: "2.2".to_string(),
+        };
+        assert_eq!(header.to_string(), "ACMI text v2.2");
+        header.file_type = "text/acmi/fork".to_string();
+        assert_eq!(header.to_string(), "text/acmi/fork v2.2");
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn test_ignore_properties() {
+        let acmi =
+            b"FileType=text/acmi/tacview\nFileVersion=2.2\nA,RadarMode=1,Name=a,FuelWeight=1\n";
+        let mut reader = RealTimeReader::try_from_reader(&acmi[..])
+            .await
+            .unwrap()
+            .ignore_properties(vec!["Radar".to_string(), "Fuel".to_string()]);
+        assert_eq!(
+            reader.next().await.unwrap(),
+            Some(Record::Update(
+                0xA,
+                vec![record::object_property::ObjectProperty::Name("a".into())]
+            ))
+        );
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn test_four_field_heading() {
+        const ACMI: &[u8] = b"FileType=text/acmi/tacview\nFileVersion=2.2\nA,T=1|2|3|185\n";
+        let expected = |coords| Record::Update(0xA, vec![ObjectProperty::T(coords)]);
+        let position = Coords::position(1.0, 2.0, 3.0);
+
+        let mut reader = RealTimeReader::try_from_reader(ACMI).await.unwrap();
+        assert_eq!(
+            reader.next().await.unwrap(),
+            Some(expected(Coords {
+                roll: Some(185.0),
+                ..position.clone()
+            }))
+        );
+
+        let mut reader = RealTimeReader::try_from_reader(ACMI)
+            .await
+            .unwrap()
+            .four_field_heading(true);
+        assert_eq!(
+            reader.next().await.unwrap(),
+            Some(expected(Coords {
+                heading: Some(185.0),
+                ..position
+            }))
+        );
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn test_reject_duplicates() {
+        const ACMI: &[u8] =
+            b"FileType=text/acmi/tacview\nFileVersion=2.2\nA,Name=F-16C-52,Name=F-15C\n";
+        let mut reader = RealTimeReader::try_from_reader(ACMI).await.unwrap();
+        assert_eq!(
+            reader.next().await.unwrap(),
+            Some(Record::Update(
+                0xA,
+                vec![
+                    ObjectProperty::Name("F-16C-52".into()),
+                    ObjectProperty::Name("F-15C".into()),
+                ]
+            ))
+        );
+
+        let mut reader = RealTimeReader::try_from_reader(ACMI)
+            .await
+            .unwrap()
+            .reject_duplicates(true);
+        assert!(matches!(
+            reader.next().await,
+            Err(Error::DuplicateProperty(_))
+        ));
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn test_sanitize() {
+        let acmi = b"FileType=text/acmi/tacview\nFileVersion=2.2\nA,Name=F-16\x00C\n";
+        let mut reader = RealTimeReader::try_from_reader(&acmi[..]).await.unwrap();
+        assert_eq!(
+            reader.next().await.unwrap().unwrap().to_string(),
+            "A,Name=F-16\u{0}C"
+        );
+
+        let mut reader = RealTimeReader::try_from_reader(&acmi[..])
+            .await
+            .unwrap()
+            .sanitize(Sanitize::Strip);
+        assert_eq!(
+            reader.next().await.unwrap().unwrap().to_string(),
+            "A,Name=F-16C"
+        );
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn test_closed() {
+        let acmi = b"FileType=text/acmi/tacview\nFileVersion=2.2\n#1\n#2\n";
+        let mut reader = RealTimeReader::try_from_reader(&acmi[..]).await.unwrap();
+        assert!(!reader.is_closed());
+        assert_eq!(reader.next().await.unwrap(), Some(Record::Frame(1.0)));
+        reader.close();
+        assert!(reader.is_closed());
+        assert_eq!(reader.next().await.unwrap(), None);
+
+        let mut reader = RealTimeReader::try_from_reader(&acmi[..]).await.unwrap();
+        while reader.next().await.unwrap().is_some() {}
+        assert!(reader.is_closed());
+        for _ in 0..3 {
+            assert_eq!(reader.next().await.unwrap(), None);
+        }
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn test_from_async_read() {
+        let acmi = b"FileType=text/acmi/tacview\nFileVersion=2.2\n#1\n";
+        let mut reader = from_async_read(&acmi[..]).await.unwrap();
+        assert_eq!(reader.header.file_version, "2.2");
+        assert_eq!(reader.next().await.unwrap(), Some(Record::Frame(1.0)));
+        assert_eq!(reader.next().await.unwrap(), None);
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn test_only_objects() {
+        let acmi = b"FileType=text/acmi/tacview\nFileVersion=2.2
+0,ReferenceLongitude=-129
+#1
+A,T=1|2|3
+B,T=4|5|6
+0,Event=Destroyed|B|
+-B
+-A
+";
+        let mut reader = RealTimeReader::try_from_reader(&acmi[..])
+            .await
+            .unwrap()
+            .only_objects(HashSet::from([0xA]));
+        let mut records = Vec::new();
+        while let Some(record) = reader.next().await.unwrap() {
+            records.push(record.to_string());
+        }
+        assert_eq!(
+            records,
+            vec![
+                "0,ReferenceLongitude=-129",
+                "#1",
+                "A,T=1|2|3",
+                "0,Event=Destroyed|B|",
+                "-A",
+            ]
+        );
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn test_header_validator() {
+        let acmi = b"FileType=text/acmi/tacview\nFileVersion=2.1\n#1\n";
+        assert!(matches!(
+            RealTimeReader::try_from_reader(&acmi[..]).await,
+            Err(Error::BadAcmiFileVersion(version)) if version == "2.1"
+        ));
+
+        let mut reader = RealTimeReader::try_from_reader_with_validator(&acmi[..], |_, _| Ok(()))
+            .await
+            .unwrap();
+        assert_eq!(reader.header.file_version, "2.1");
+        assert_eq!(reader.next().await.unwrap(), Some(Record::Frame(1.0)));
+
+        let acmi = b"FileType=text/acmi/tacview\nFileVersion=2.2\n";
+        let strict = |_: &str, file_version: &str| {
+            if file_version != "2.2.1" {
+                return Err(Error::BadAcmiFileVersion(file_version.to_string()));
+            }
+            Ok(())
+        };
+        assert!(
+            RealTimeReader::try_from_reader_with_validator(&acmi[..], strict)
+                .await
+                .is_err()
+        );
+
+        let error = RealTimeReader::try_from_reader(&b"FileType=text/acmi/tacview\n"[..])
+            .await
+            .unwrap_err();
+        assert!(matches!(error, Error::AcmiReaderRead(_)));
+        assert!(error.is_recoverable());
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn test_stream_file() {
+        let dir = TempDir::new("test-stream-file");
+        let path = dir.path().join("test.txt.acmi");
+        std::fs::write(
+            &path,
+            "FileType=text/acmi/tacview\nFileVersion=2.2\n#1\n-A\n",
+        )
+        .unwrap();
+        let mut reader = stream_file(&path).await.unwrap();
+        assert_eq!(reader.next().await.unwrap(), Some(Record::Frame(1.0)));
+        assert_eq!(reader.next().await.unwrap(), Some(Record::Remove(0xA)));
+        assert_eq!(reader.next().await.unwrap(), None);
+
+        std::fs::write(&path, b"PK\x03\x04").unwrap();
+        assert!(matches!(
+            stream_file(&path).await,
+            Err(Error::AcmiReaderRead(e)) if e.kind() == std::io::ErrorKind::InvalidData
+        ));
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn test_stream_file_bounded_memory() {
+        use std::io::Write;
+
+        use tokio::io::AsyncSeekExt;
+
+        let dir = TempDir::new("test-stream-file-bounded-memory");
+        let path = dir.path().join("test.txt.acmi");
+        let mut file = std::io::BufWriter::new(std::fs::File::create(&path).unwrap());
+        file.write_all(b"FileType=text/acmi/tacview\nFileVersion=2.2\n")
+            .unwrap();
+        for frame in 0..100_000 {
+            writeln!(file, "#{frame}\nA,T=1|2|3").unwrap();
+        }
+        file.into_inner().unwrap().sync_all().unwrap();
+        assert!(std::fs::metadata(&path).unwrap().len() > 1 << 20);
+
+        let mut reader = stream_file(&path).await.unwrap();
+        for _ in 0..10 {
+            reader.next().await.unwrap().unwrap();
+        }
+        let position = reader.get_mut().get_mut().stream_position().await.unwrap();
+        assert!(position < 64 << 10);
+
+        let mut count = 10;
+        while reader.next().await.unwrap().is_some() {
+            count += 1;
+        }
+        assert_eq!(count, 200_000);
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn test_tracing_fields() {
+        let capture = CaptureSubscriber::default();
+        let _guard = tracing::subscriber::set_default(capture.clone());
+
+        let mut reader = RealTimeReader::try_from_reader(
+            &b"FileType=text/acmi/tacview\nFileVersion=2.2\n#1\nA,T=1|2|3\n#x\n"[..],
+        )
+        .await
+        .unwrap();
+        reader.next().await.unwrap();
+        reader.next().await.unwrap();
+        assert!(reader.next().await.is_err());
+
+        let fields = capture.fields();
+        for expected in [
+            "record_kind=Frame",
+            "frame_time=1.0",
+            "object_id=A",
+            "record_kind=Update",
+            "line=#x",
+            "error=failed to parse float: invalid float literal",
+        ] {
+            assert!(
+                fields.iter().any(|f| f == expected),
+                "{expected} in {fields:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_line_fuzz() {
+        assert_eq!(parse_line_fuzz(b"#1").unwrap(), Record::Frame(1.0));
+        for data in [
+            &b""[..],
+            b"#",
+            b"A,T=",
+            b"A,T=|||||||||||",
+            b"0,Event=",
+            b"0,Event=Timeout|",
+            b"\xff,\xfe=\x00",
+            b"A,\\",
+        ] {
+            let _ = parse_line_fuzz(data);
+        }
+    }
+}
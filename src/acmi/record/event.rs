@@ -1,10 +1,10 @@
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 
 use serde::{Deserialize, Serialize};
 
 use crate::error::{Error, Result};
 
-use super::parse_object_id;
+use super::{format_object_id, parse_object_id};
 
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase", tag = "type", content = "value")]
@@ -52,8 +52,50 @@ pub enum Event {
     /// `Event=Timeout|SourceId:507|AmmoType:FOX2|AmmoCount:1|Bullseye:50/15000/2500|TargetId:201|IntendedTarget:Leader|Outcome:Kill`
     Timeout(TimeoutEvent),
 
-    /// Unknown event. `(type, message)`
-    Unknown(String, String),
+    /// Unknown event, with its pipe-separated fields kept apart.  
+    /// `Event=Refueled|507|4000` gives `ty` `Refueled` and `fields`
+    /// `["507", "4000"]`
+    Unknown { ty: String, fields: Vec<String> },
+}
+
+impl Event {
+    /// Type of the event as written after `Event=`, e.g. `Destroyed`.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Message(..) => "Message",
+            Self::Bookmark(_) => "Bookmark",
+            Self::Debug(_) => "Debug",
+            Self::LeftArea(_) => "LeftArea",
+            Self::Destroyed(_) => "Destroyed",
+            Self::TakenOff(..) => "TakenOff",
+            Self::Landed(..) => "Landed",
+            Self::Timeout(_) => "Timeout",
+            Self::Unknown { ty, .. } => ty,
+        }
+    }
+
+    /// Rewrites the id of every object the event refers to, including the
+    /// hexadecimal `SourceId` and `TargetId` of `Timeout` events.
+    pub(crate) fn map_object_ids(&mut self, f: impl Fn(u64) -> u64) {
+        match self {
+            Self::Message(id, _)
+            | Self::LeftArea(id)
+            | Self::Destroyed(id)
+            | Self::TakenOff(id, _)
+            | Self::Landed(id, _) => *id = f(*id),
+            Self::Timeout(timeout) => {
+                for id in [&mut timeout.source_id, &mut timeout.target_id]
+                    .into_iter()
+                    .flatten()
+                {
+                    if let Ok(parsed) = parse_object_id(id) {
+                        *id = format_object_id(f(parsed));
+                    }
+                }
+            }
+            Self::Bookmark(_) | Self::Debug(_) | Self::Unknown { .. } => {}
+        }
+    }
 }
 
 impl FromStr for Event {
@@ -131,11 +173,35 @@ impl FromStr for Event {
                 Ok(Self::Timeout(timeout))
             }
             _ => {
-                let (ty, message) = s.split_once('|').unwrap_or((s, ""));
-                let (_, ty) = ty
+                let (_, ty) = event_type
                     .split_once('=')
                     .ok_or_else(|| Error::MalformedEvent(s.to_string()))?;
-                Ok(Self::Unknown(ty.to_string(), message.to_string()))
+                Ok(Self::Unknown {
+                    ty: ty.to_string(),
+                    fields: tokens.map(str::to_string).collect(),
+                })
+            }
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Message(id, message) => write!(f, "Event=Message|{id:X}|{message}"),
+            Self::Bookmark(message) => write!(f, "Event=Bookmark|{message}"),
+            Self::Debug(message) => write!(f, "Event=Debug|{message}"),
+            Self::LeftArea(id) => write!(f, "Event=LeftArea|{id:X}|"),
+            Self::Destroyed(id) => write!(f, "Event=Destroyed|{id:X}|"),
+            Self::TakenOff(id, message) => write!(f, "Event=TakenOff|{id:X}|{message}"),
+            Self::Landed(id, message) => write!(f, "Event=Landed|{id:X}|{message}"),
+            Self::Timeout(timeout) => write!(f, "Event=Timeout{timeout}"),
+            Self::Unknown { ty, fields } => {
+                write!(f, "Event={ty}")?;
+                for field in fields {
+                    write!(f, "|{field}")?;
+                }
+                Ok(())
             }
         }
     }
@@ -193,3 +259,52 @@ impl TimeoutEvent {
         })
     }
 }
+
+impl fmt::Display for TimeoutEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fields = [
+            ("SourceId", &self.source_id),
+            ("AmmoType", &self.ammo_type),
+            ("AmmoCount", &self.ammo_count),
+            ("Bullseye", &self.bullseye),
+            ("TargetId", &self.target_id),
+            ("IntendedTarget", &self.intended_target),
+            ("Outcome", &self.outcome),
+        ];
+        for (name, value) in fields {
+            if let Some(value) = value {
+                write!(f, "|{name}:{value}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_unknown_fields() {
+        let event = Event::from_str("Event=Refueled|507|4000|").unwrap();
+        assert_eq!(
+            event,
+            Event::Unknown {
+                ty: "Refueled".to_string(),
+                fields: vec!["507".to_string(), "4000".to_string(), String::new()],
+            }
+        );
+        assert_eq!(event.name(), "Refueled");
+        assert_eq!(event.to_string(), "Event=Refueled|507|4000|");
+
+        let event = Event::from_str("Event=Ping").unwrap();
+        assert_eq!(
+            event,
+            Event::Unknown {
+                ty: "Ping".to_string(),
+                fields: vec![],
+            }
+        );
+        assert_eq!(event.to_string(), "Event=Ping");
+    }
+}
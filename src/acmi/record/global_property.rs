@@ -1,10 +1,12 @@
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 
 use serde::{Deserialize, Serialize};
 use time::{format_description::well_known::Rfc3339, OffsetDateTime};
 
 use crate::error::Error;
 
+use super::{approx_eq_f64, PropertyKey, UnknownValue};
+
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase", tag = "type", content = "value")]
 pub enum GlobalProperty {
@@ -57,7 +59,46 @@ pub enum GlobalProperty {
     ReferenceLatitude(f64),
 
     /// Unknown global property. `(name, value)`
-    Unknown(String, String),
+    Unknown(String, UnknownValue),
+}
+
+impl GlobalProperty {
+    /// Key of the property in a [`World`](crate::acmi::world::World),
+    /// distinguishing unknown properties by name.
+    pub fn key(&self) -> PropertyKey<Self> {
+        match self {
+            Self::Unknown(name, _) => PropertyKey::Unknown(name.clone()),
+            property => PropertyKey::Known(std::mem::discriminant(property)),
+        }
+    }
+
+    /// Text value of the property, if it has one.
+    pub(crate) fn text_mut(&mut self) -> Option<&mut String> {
+        match self {
+            Self::DataSource(value)
+            | Self::DataRecorder(value)
+            | Self::Author(value)
+            | Self::Title(value)
+            | Self::Category(value)
+            | Self::Briefing(value)
+            | Self::Debriefing(value)
+            | Self::Comments(value)
+            | Self::Unknown(_, UnknownValue(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Same as `==`, with the reference longitude and latitude compared
+    /// within `epsilon`.
+    pub fn approx_eq(&self, other: &Self, epsilon: f64) -> bool {
+        match (self, other) {
+            (Self::ReferenceLongitude(a), Self::ReferenceLongitude(b))
+            | (Self::ReferenceLatitude(a), Self::ReferenceLatitude(b)) => {
+                approx_eq_f64(*a, *b, epsilon)
+            }
+            _ => self == other,
+        }
+    }
 }
 
 impl FromStr for GlobalProperty {
@@ -100,7 +141,33 @@ impl FromStr for GlobalProperty {
             let (name, value) = s
                 .split_once('=')
                 .ok_or_else(|| Error::MalformedGlobalProperty(s.to_string()))?;
-            Ok(Self::Unknown(name.to_string(), value.to_string()))
+            Ok(Self::Unknown(name.to_string(), value.into()))
+        }
+    }
+}
+
+impl fmt::Display for GlobalProperty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DataSource(value) => write!(f, "DataSource={value}"),
+            Self::DataRecorder(value) => write!(f, "DataRecorder={value}"),
+            Self::ReferenceTime(value) => {
+                let value = value.format(&Rfc3339).map_err(|_| fmt::Error)?;
+                write!(f, "ReferenceTime={value}")
+            }
+            Self::RecordingTime(value) => {
+                let value = value.format(&Rfc3339).map_err(|_| fmt::Error)?;
+                write!(f, "RecordingTime={value}")
+            }
+            Self::Author(value) => write!(f, "Author={value}"),
+            Self::Title(value) => write!(f, "Title={value}"),
+            Self::Category(value) => write!(f, "Category={value}"),
+            Self::Briefing(value) => write!(f, "Briefing={value}"),
+            Self::Debriefing(value) => write!(f, "Debriefing={value}"),
+            Self::Comments(value) => write!(f, "Comments={value}"),
+            Self::ReferenceLongitude(value) => write!(f, "ReferenceLongitude={value}"),
+            Self::ReferenceLatitude(value) => write!(f, "ReferenceLatitude={value}"),
+            Self::Unknown(name, value) => write!(f, "{name}={value}"),
         }
     }
 }
@@ -2,19 +2,144 @@ pub mod event;
 pub mod global_property;
 pub mod object_property;
 
-use std::str::FromStr;
+use std::{
+    collections::HashSet,
+    fmt::{self, Write},
+    hash::{Hash, Hasher},
+    mem::Discriminant,
+    str::FromStr,
+    sync::Arc,
+};
 
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
 use crate::error::{Error, Result};
 
-use self::{event::Event, global_property::GlobalProperty, object_property::ObjectProperty};
+use self::{
+    event::Event,
+    global_property::GlobalProperty,
+    object_property::{Coords, ObjectProperty},
+};
 
-fn parse_object_id(id: &str) -> Result<u64> {
+/// Identity of a property in a state: its kind, or its name for unknown
+/// properties, which all share the same kind. See [`ObjectProperty::key`]
+/// and [`GlobalProperty::key`].
+pub enum PropertyKey<T> {
+    Known(Discriminant<T>),
+    Unknown(String),
+}
+
+impl<T> fmt::Debug for PropertyKey<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Known(discriminant) => f.debug_tuple("Known").field(discriminant).finish(),
+            Self::Unknown(name) => f.debug_tuple("Unknown").field(name).finish(),
+        }
+    }
+}
+
+impl<T> Clone for PropertyKey<T> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Known(discriminant) => Self::Known(*discriminant),
+            Self::Unknown(name) => Self::Unknown(name.clone()),
+        }
+    }
+}
+
+impl<T> PartialEq for PropertyKey<T> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Known(a), Self::Known(b)) => a == b,
+            (Self::Unknown(a), Self::Unknown(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl<T> Eq for PropertyKey<T> {}
+
+impl<T> Hash for PropertyKey<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Self::Known(discriminant) => discriminant.hash(state),
+            Self::Unknown(name) => name.hash(state),
+        }
+    }
+}
+
+/// Parses a hexadecimal object id, like `2D50A7`.
+pub fn parse_object_id(id: &str) -> Result<u64> {
     u64::from_str_radix(id, 16).map_err(Error::ParseInt)
 }
 
+/// Formats an object id the way records are serialized: uppercase
+/// hexadecimal without leading zeros, like `2D50A7`.
+pub fn format_object_id(id: u64) -> String {
+    format!("{id:X}")
+}
+
+/// Raw value of a property unknown to this crate. Its type is unknown too, so
+/// it is kept as received and only parsed on demand.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct UnknownValue(String);
+
+impl UnknownValue {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        self.0.parse().ok()
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        self.0.parse().ok()
+    }
+}
+
+impl From<&str> for UnknownValue {
+    fn from(value: &str) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl From<String> for UnknownValue {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl fmt::Display for UnknownValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Parses a boolean property value. The specification uses `1` and `0`, but
+/// some exporters write `1.0` or `true`/`false`; those are accepted too, the
+/// textual forms with a warning. Anything else is considered `false`.
+fn parse_bool(key: &str, value: &str) -> bool {
+    match value {
+        "1" | "1.0" => true,
+        "0" | "0.0" => false,
+        _ if value.eq_ignore_ascii_case("true") => {
+            tracing::warn!(key, value, "coercing non-numeric boolean property");
+            true
+        }
+        _ if value.eq_ignore_ascii_case("false") => {
+            tracing::warn!(key, value, "coercing non-numeric boolean property");
+            false
+        }
+        _ => false,
+    }
+}
+
+/// Serialized with serde as `{"type": "<variant>", "value": ...}`, with
+/// camelCase variant and field names, the same way as the nested events and
+/// properties. This representation is a stable contract, pinned by tests.
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase", tag = "type", content = "value")]
 pub enum Record {
@@ -22,26 +147,307 @@ pub enum Record {
     Frame(f64),
     Event(Event),
     GlobalProperties(Vec<GlobalProperty>),
+    /// Properties are kept in the order they appear in the line, including
+    /// any property sent more than once.
     Update(u64, Vec<ObjectProperty>),
 }
 
+/// Callbacks for each kind of [`Record`], see [`Record::accept`]. Every
+/// method does nothing by default, so visitors only implement the kinds they
+/// handle.
+pub trait RecordVisitor {
+    fn on_frame(&mut self, _time: f64) {}
+
+    fn on_update(&mut self, _id: u64, _properties: &[ObjectProperty]) {}
+
+    fn on_remove(&mut self, _id: u64) {}
+
+    fn on_event(&mut self, _event: &Event) {}
+
+    fn on_global(&mut self, _properties: &[GlobalProperty]) {}
+}
+
+pub(crate) fn approx_eq_f64(a: f64, b: f64, epsilon: f64) -> bool {
+    a == b || (a - b).abs() <= epsilon
+}
+
+/// How [`Record::sanitize`] handles control characters in text values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sanitize {
+    /// Removes them.
+    Strip,
+    /// Replaces each of them with the given character.
+    Replace(char),
+}
+
+impl Sanitize {
+    fn apply(self, value: &mut String) -> bool {
+        // escaped end-of-lines are legitimate in multi-line values
+        let is_dirty = |c: char| c.is_control() && c != '\n';
+        if !value.contains(is_dirty) {
+            return false;
+        }
+        *value = match self {
+            Self::Strip => value.chars().filter(|c| !is_dirty(*c)).collect(),
+            Self::Replace(replacement) => value
+                .chars()
+                .map(|c| if is_dirty(c) { replacement } else { c })
+                .collect(),
+        };
+        true
+    }
+}
+
+/// Cache of text values shared between records, so that e.g. the names,
+/// groups and coalitions of many objects point to the same allocation, see
+/// [`Record::intern`].
+#[derive(Debug, Default)]
+pub struct Interner {
+    values: HashSet<Arc<str>>,
+}
+
+impl Interner {
+    /// Maximum number of distinct values cached, later ones are kept as is.
+    const CAPACITY: usize = 4096;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn intern(&mut self, value: &mut Arc<str>) {
+        if let Some(interned) = self.values.get(&**value) {
+            *value = interned.clone();
+        } else if self.values.len() < Self::CAPACITY {
+            self.values.insert(value.clone());
+        }
+    }
+}
+
+/// Semantically suspicious content of a record which parsed fine, see
+/// [`Record::validate`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Warning {
+    /// `0` is the id of global records, not of an object.
+    ZeroObjectId,
+    /// Latitude outside `[-90, 90]` or longitude outside `[-180, 180]`, as
+    /// received, without the reference.
+    CoordsOutOfRange { id: u64, coords: Coords },
+    /// An event refers to an object which does not exist. Only detected by
+    /// [`World`](crate::acmi::world::World), which knows the objects.
+    UnknownObject { id: u64 },
+}
+
+/// Category of a [`Record`], without its payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RecordKind {
+    Remove,
+    Frame,
+    Event,
+    GlobalProperties,
+    Update,
+}
+
 impl FromStr for Record {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse::<&str>(s, None, &[], false, false)
+    }
+}
+
+impl Record {
+    /// Checks the record for suspicious content which is not a parse error.
+    pub fn validate(&self) -> Vec<Warning> {
+        let mut warnings = Vec::new();
+        match self {
+            Self::Remove(0) => warnings.push(Warning::ZeroObjectId),
+            Self::Update(id, object_properties) => {
+                if *id == 0 {
+                    warnings.push(Warning::ZeroObjectId);
+                }
+                for object_property in object_properties {
+                    let ObjectProperty::T(coords) = object_property else {
+                        continue;
+                    };
+                    let longitude = coords.longitude.unwrap_or_default();
+                    let latitude = coords.latitude.unwrap_or_default();
+                    if !(-180.0..=180.0).contains(&longitude) || !(-90.0..=90.0).contains(&latitude)
+                    {
+                        warnings.push(Warning::CoordsOutOfRange {
+                            id: *id,
+                            coords: coords.clone(),
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+        warnings
+    }
+
+    /// Same as `==`, with floats compared within `epsilon`: frame times,
+    /// coordinates, and numeric properties. Events hold no floats and are
+    /// compared exactly.
+    pub fn approx_eq(&self, other: &Self, epsilon: f64) -> bool {
+        match (self, other) {
+            (Self::Frame(a), Self::Frame(b)) => approx_eq_f64(*a, *b, epsilon),
+            (Self::GlobalProperties(a), Self::GlobalProperties(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.approx_eq(b, epsilon))
+            }
+            (Self::Update(a_id, a), Self::Update(b_id, b)) => {
+                a_id == b_id
+                    && a.len() == b.len()
+                    && a.iter().zip(b).all(|(a, b)| a.approx_eq(b, epsilon))
+            }
+            _ => self == other,
+        }
+    }
+
+    /// Strips or replaces control characters, e.g. stray NULs, in the text
+    /// values of the properties. End-of-lines are kept. Returns whether any
+    /// value changed.
+    pub fn sanitize(&mut self, sanitize: Sanitize) -> bool {
+        let values: Vec<&mut String> = match self {
+            Self::Update(_, properties) => properties
+                .iter_mut()
+                .filter_map(ObjectProperty::text_mut)
+                .collect(),
+            Self::GlobalProperties(properties) => properties
+                .iter_mut()
+                .filter_map(GlobalProperty::text_mut)
+                .collect(),
+            Self::Remove(_) | Self::Frame(_) | Self::Event(_) => Vec::new(),
+        };
+        let mut changed = values
+            .into_iter()
+            .fold(false, |changed, value| sanitize.apply(value) | changed);
+        if let Self::Update(_, properties) = self {
+            for value in properties
+                .iter_mut()
+                .filter_map(ObjectProperty::shared_text_mut)
+            {
+                let mut text = value.to_string();
+                if sanitize.apply(&mut text) {
+                    *value = text.into();
+                    changed = true;
+                }
+            }
+        }
+        changed
+    }
+
+    /// Replaces the shared text values of the record, like names and
+    /// coalitions, with the ones already cached by `interner`.
+    pub fn intern(&mut self, interner: &mut Interner) {
+        if let Self::Update(_, properties) = self {
+            for value in properties
+                .iter_mut()
+                .filter_map(ObjectProperty::shared_text_mut)
+            {
+                interner.intern(value);
+            }
+        }
+    }
+
+    /// Rewrites the id of every object of the record: the updated or
+    /// removed object, the objects its properties refer to, and the objects
+    /// of events. The global id `0` is kept.
+    pub fn map_object_ids(mut self, f: impl Fn(u64) -> u64) -> Self {
+        let f = |id| if id == 0 { 0 } else { f(id) };
+        match &mut self {
+            Self::Remove(id) => *id = f(*id),
+            Self::Update(id, object_properties) => {
+                *id = f(*id);
+                for id in object_properties
+                    .iter_mut()
+                    .filter_map(ObjectProperty::object_id_mut)
+                {
+                    *id = f(*id);
+                }
+            }
+            Self::Event(event) => event.map_object_ids(f),
+            Self::Frame(_) | Self::GlobalProperties(_) => {}
+        }
+        self
+    }
+
+    /// Calls the method of the visitor matching the kind of the record.
+    pub fn accept(&self, visitor: &mut impl RecordVisitor) {
+        match self {
+            Self::Remove(id) => visitor.on_remove(*id),
+            Self::Frame(time) => visitor.on_frame(*time),
+            Self::Event(event) => visitor.on_event(event),
+            Self::GlobalProperties(properties) => visitor.on_global(properties),
+            Self::Update(id, properties) => visitor.on_update(*id, properties),
+        }
+    }
+
+    pub fn kind(&self) -> RecordKind {
+        match self {
+            Self::Remove(_) => RecordKind::Remove,
+            Self::Frame(_) => RecordKind::Frame,
+            Self::Event(_) => RecordKind::Event,
+            Self::GlobalProperties(_) => RecordKind::GlobalProperties,
+            Self::Update(_, _) => RecordKind::Update,
+        }
+    }
+
+    /// Parses a record, skipping the property tokens which fail to parse
+    /// instead of failing the whole record. The skipped tokens are returned
+    /// with their errors, so e.g. reference times keep updating even when a
+    /// single global property is malformed. Empty numeric values, like
+    /// `HDG=`, are dropped with a warning instead of being reported.
+    pub fn from_str_lenient(s: &str) -> Result<(Self, Vec<(String, Error)>)> {
+        let mut errors = Vec::new();
+        let record = Self::parse::<&str>(s, Some(&mut errors), &[], false, false)?;
+        Ok((record, errors))
+    }
+
+    /// Parses a record, skipping the object properties whose name starts
+    /// with one of the prefixes, e.g. `Radar` or `Fuel`, before they are
+    /// parsed and stored.
+    pub fn from_str_ignoring<P: AsRef<str>>(s: &str, ignored: &[P]) -> Result<Self> {
+        Self::parse(s, None, ignored, false, false)
+    }
+
+    /// Parses an update sending the same property more than once, e.g.
+    /// `A,Name=F-16,Name=F-15`, as [`Error::DuplicateProperty`] instead of
+    /// keeping every value, of which the last one wins when applied.
+    pub fn from_str_rejecting_duplicates(s: &str) -> Result<Self> {
+        Self::parse::<&str>(s, None, &[], false, true)
+    }
+
+    /// Reads the fourth field of four-field coordinates as heading if
+    /// `four_field_heading`, see [`Coords`], and fails on a property sent
+    /// more than once in an update if `reject_duplicates`.
+    pub(crate) fn parse<P: AsRef<str>>(
+        s: &str,
+        mut errors: Option<&mut Vec<(String, Error)>>,
+        ignored: &[P],
+        four_field_heading: bool,
+        reject_duplicates: bool,
+    ) -> Result<Self> {
         // remove
         if let Some(line) = s.strip_prefix('-') {
             let id = parse_object_id(line)?;
             return Ok(Record::Remove(id));
         }
 
-        // time frame
+        // time frame, negative offsets from the reference time are allowed
         if let Some(line) = s.strip_prefix('#') {
+            if line.is_empty() {
+                return Err(Error::MalformedFrame(s.to_string()));
+            }
             let timeframe = f64::from_str(line).map_err(Error::ParseFloat)?;
+            if !timeframe.is_finite() {
+                return Err(Error::MalformedFrame(s.to_string()));
+            }
             return Ok(Record::Frame(timeframe));
         }
 
-        let (id, rest) = s.split_once(',').ok_or(Error::AcmiReaderEol)?;
+        let (id, rest) = s
+            .split_once(',')
+            .ok_or_else(|| Error::UnexpectedLine(s.to_string()))?;
 
         if id == "0" {
             if rest.starts_with("Event=") {
@@ -50,19 +456,198 @@ impl FromStr for Record {
             } else {
                 let global_properties = parse_comma(rest)
                     .into_iter()
-                    .map(|token| GlobalProperty::from_str(&token))
+                    .filter_map(|token| {
+                        let result = GlobalProperty::from_str(&token);
+                        collect_error(token, result, errors.as_deref_mut())
+                    })
                     .try_collect()?;
                 Ok(Self::GlobalProperties(global_properties))
             }
         } else {
             let id = parse_object_id(id)?;
-            let object_properties = parse_comma(rest)
-                .into_iter()
-                .map(|token| ObjectProperty::from_str(&token))
-                .try_collect()?;
+            let mut object_properties: Vec<ObjectProperty> = Vec::new();
+            for token in parse_comma(rest) {
+                let name = token
+                    .split_once('=')
+                    .map_or(token.as_str(), |(name, _)| name);
+                if ignored
+                    .iter()
+                    .any(|prefix| name.starts_with(prefix.as_ref()))
+                {
+                    continue;
+                }
+                let result = match (token.strip_prefix("T="), errors.as_deref_mut()) {
+                    // keep the valid components of malformed coordinates
+                    (Some(value), Some(errors)) => {
+                        let mut coords_errors = Vec::new();
+                        Coords::parse(value, Some(&mut coords_errors), four_field_heading).map(
+                            |coords| {
+                                errors
+                                    .extend(coords_errors.into_iter().map(|e| (token.clone(), e)));
+                                ObjectProperty::T(coords)
+                            },
+                        )
+                    }
+                    (Some(value), None) => {
+                        Coords::parse(value, None, four_field_heading).map(ObjectProperty::T)
+                    }
+                    _ => ObjectProperty::from_str(&token),
+                };
+                let result = match result {
+                    // An empty numeric value carries no information, so the
+                    // lenient parser treats it as if the property was absent.
+                    Err(Error::ParseFloat(_) | Error::ParseInt(_))
+                        if errors.is_some() && token.ends_with('=') =>
+                    {
+                        tracing::warn!(token, "ignoring empty numeric property");
+                        continue;
+                    }
+                    result => collect_error(token, result, errors.as_deref_mut()),
+                };
+                if let Some(object_property) = result {
+                    let object_property = object_property?;
+                    if reject_duplicates
+                        && object_properties
+                            .iter()
+                            .any(|p| p.key() == object_property.key())
+                    {
+                        return Err(Error::DuplicateProperty(s.to_string()));
+                    }
+                    object_properties.push(object_property);
+                }
+            }
             Ok(Self::Update(id, object_properties))
         }
     }
+
+    /// Writes the record as an ACMI line, with the trailing end-of-line,
+    /// without allocating an intermediate string.
+    pub fn write_acmi<W>(&self, writer: &mut W) -> std::io::Result<()>
+    where
+        W: std::io::Write,
+    {
+        writeln!(writer, "{self}")
+    }
+}
+
+/// Fluent builder of [`Record::Update`], holding at most one property of each
+/// kind. Coordinates are always written first, the other properties in the
+/// order they were first set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpdateBuilder {
+    id: u64,
+    properties: Vec<ObjectProperty>,
+}
+
+impl UpdateBuilder {
+    pub fn new(id: u64) -> Self {
+        Self {
+            id,
+            properties: Vec::new(),
+        }
+    }
+
+    pub fn coords(self, coords: Coords) -> Self {
+        self.property(ObjectProperty::T(coords))
+    }
+
+    /// Sets a property, replacing any previously set property of the same
+    /// [key](ObjectProperty::key).
+    pub fn property(mut self, property: ObjectProperty) -> Self {
+        let key = property.key();
+        match self.properties.iter_mut().find(|p| p.key() == key) {
+            Some(existing) => *existing = property,
+            None => self.properties.push(property),
+        }
+        self
+    }
+
+    pub fn build(mut self) -> Record {
+        self.properties
+            .sort_by_key(|p| !matches!(p, ObjectProperty::T(_)));
+        Record::Update(self.id, self.properties)
+    }
+}
+
+/// Writes the record as an ACMI line, without the trailing end-of-line. Floats
+/// are written in their shortest representation that parses back to the exact
+/// same value.
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Remove(id) => write!(f, "-{id:X}"),
+            Self::Frame(time) => write!(f, "#{time}"),
+            Self::Event(event) => {
+                write!(f, "0,")?;
+                write!(Escape::eol(f), "{event}")
+            }
+            Self::GlobalProperties(global_properties) => {
+                write!(f, "0,")?;
+                write_comma(f, global_properties)
+            }
+            Self::Update(id, object_properties) => {
+                write!(f, "{id:X},")?;
+                write_comma(f, object_properties)
+            }
+        }
+    }
+}
+
+/// Escapes end-of-lines, and optionally commas, with a backslash while writing.
+struct Escape<'a, 'b> {
+    f: &'a mut fmt::Formatter<'b>,
+    comma: bool,
+}
+
+impl<'a, 'b> Escape<'a, 'b> {
+    fn eol(f: &'a mut fmt::Formatter<'b>) -> Self {
+        Self { f, comma: false }
+    }
+
+    fn eol_and_comma(f: &'a mut fmt::Formatter<'b>) -> Self {
+        Self { f, comma: true }
+    }
+}
+
+impl fmt::Write for Escape<'_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut rest = s;
+        while let Some(i) = rest.find(|c| c == '\n' || (self.comma && c == ',')) {
+            self.f.write_str(&rest[..i])?;
+            self.f.write_char('\\')?;
+            self.f.write_str(&rest[i..i + 1])?;
+            rest = &rest[i + 1..];
+        }
+        self.f.write_str(rest)
+    }
+}
+
+/// Moves a token error into `errors` if given, to skip the token.
+fn collect_error<T>(
+    token: String,
+    result: Result<T>,
+    errors: Option<&mut Vec<(String, Error)>>,
+) -> Option<Result<T>> {
+    match (result, errors) {
+        (Err(e), Some(errors)) => {
+            errors.push((token, e));
+            None
+        }
+        (result, _) => Some(result),
+    }
+}
+
+fn write_comma<T>(f: &mut fmt::Formatter<'_>, tokens: &[T]) -> fmt::Result
+where
+    T: fmt::Display,
+{
+    for (i, token) in tokens.iter().enumerate() {
+        if i > 0 {
+            f.write_char(',')?;
+        }
+        write!(Escape::eol_and_comma(f), "{token}")?;
+    }
+    Ok(())
 }
 
 fn parse_comma(line: &str) -> Vec<String> {
@@ -114,4 +699,458 @@ mod test {
         ];
         assert_eq!(parse_comma(line), expected);
     }
+
+    #[test]
+    fn test_float_round_trip() {
+        for line in [
+            "#0.1",
+            "#1.5",
+            "A,T=-129.123456789|43.5|1500,HDG=185",
+            "A,Visible=0.30000000000000004",
+        ] {
+            assert_eq!(Record::from_str(line).unwrap().to_string(), line);
+        }
+
+        let record = Record::Frame(0.1 + 0.2);
+        assert_eq!(record.to_string(), "#0.30000000000000004");
+        assert_eq!(Record::from_str(&record.to_string()).unwrap(), record);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let fixture = [
+            (Record::Remove(0x2D50A7), "-2D50A7"),
+            (Record::Frame(12.5), "#12.5"),
+            (
+                Record::Event(Event::Message(0xAB, "Hello, world".to_string())),
+                "0,Event=Message|AB|Hello, world",
+            ),
+            (
+                Record::GlobalProperties(vec![
+                    GlobalProperty::Title("Counter, Attack".to_string()),
+                    GlobalProperty::ReferenceLongitude(-129.0),
+                ]),
+                "0,Title=Counter\\, Attack,ReferenceLongitude=-129",
+            ),
+            (
+                Record::Update(
+                    0xFF1A,
+                    vec![
+                        ObjectProperty::T(Coords::position(1.5, 2.0, 3.0)),
+                        ObjectProperty::Name("F-16C, Block 52".into()),
+                        ObjectProperty::Parent(0xBEEF),
+                    ],
+                ),
+                "FF1A,T=1.5|2|3,Name=F-16C\\, Block 52,Parent=BEEF",
+            ),
+        ];
+        for (record, line) in fixture {
+            assert_eq!(record.to_string(), line);
+            assert_eq!(Record::from_str(line).unwrap(), record);
+        }
+    }
+
+    #[test]
+    fn test_write_acmi() {
+        let records = [
+            "-2D50A7",
+            "#12.5",
+            "0,Event=Bookmark|Starting precautionary\\\nlanding practice",
+            "0,Title=Counter Attack,Comments=a\\,b",
+            "A,T=1|2|3,Label=x\\,y\\\nz",
+        ];
+        for line in records {
+            let record = Record::from_str(&line.replace("\\\n", "\n")).unwrap();
+            let mut buf = Vec::new();
+            record.write_acmi(&mut buf).unwrap();
+            assert_eq!(String::from_utf8(buf).unwrap(), format!("{record}\n"));
+            assert_eq!(record.to_string(), line);
+        }
+    }
+
+    #[test]
+    fn test_empty_numeric_property() {
+        assert!(matches!(
+            Record::from_str("A,HDG=,Name=F-16C-52"),
+            Err(Error::ParseFloat(_))
+        ));
+        let (record, errors) = Record::from_str_lenient("A,HDG=,Name=F-16C-52").unwrap();
+        assert_eq!(
+            record,
+            Record::Update(0xA, vec![ObjectProperty::Name("F-16C-52".into())])
+        );
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn test_update_builder() {
+        let record = UpdateBuilder::new(0x2D50A7)
+            .property(ObjectProperty::Name("F-16C-52".into()))
+            .property(ObjectProperty::Hdg(90.0))
+            .coords(Coords::from_str("1|2|3").unwrap())
+            .property(ObjectProperty::Hdg(185.0))
+            .build();
+        assert_eq!(record.to_string(), "2D50A7,T=1|2|3,Name=F-16C-52,HDG=185");
+
+        let record = UpdateBuilder::new(0xA)
+            .property(ObjectProperty::from_str("Foo=1").unwrap())
+            .property(ObjectProperty::from_str("Bar=2").unwrap())
+            .property(ObjectProperty::from_str("Foo=3").unwrap())
+            .build();
+        assert_eq!(record.to_string(), "A,Foo=3,Bar=2");
+    }
+
+    #[test]
+    fn test_parse_frame() {
+        assert_eq!(Record::from_str("#1.5e2").unwrap(), Record::Frame(150.0));
+        assert_eq!(Record::from_str("#-2.5").unwrap(), Record::Frame(-2.5));
+        assert!(matches!(
+            Record::from_str("#"),
+            Err(Error::MalformedFrame(_))
+        ));
+        assert!(matches!(
+            Record::from_str("#inf"),
+            Err(Error::MalformedFrame(_))
+        ));
+        assert!(matches!(
+            Record::from_str("#abc"),
+            Err(Error::ParseFloat(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_str_lenient() {
+        let line = "0,ReferenceTime=2011-06-02T05:00:00Z,ReferenceLongitude=abc,Title=A";
+        assert!(Record::from_str(line).is_err());
+
+        let (record, errors) = Record::from_str_lenient(line).unwrap();
+        assert_eq!(
+            record,
+            Record::GlobalProperties(vec![
+                GlobalProperty::from_str("ReferenceTime=2011-06-02T05:00:00Z").unwrap(),
+                GlobalProperty::Title("A".to_string()),
+            ])
+        );
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "ReferenceLongitude=abc");
+        assert!(matches!(errors[0].1, Error::ParseFloat(_)));
+
+        let (record, errors) = Record::from_str_lenient("A,HDG=x,Name=F-16C-52").unwrap();
+        assert_eq!(
+            record,
+            Record::Update(0xA, vec![ObjectProperty::Name("F-16C-52".into())])
+        );
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn test_lenient_coords() {
+        let line = "A,T=1|x|3|4|5|6,Name=F-16C-52";
+        assert!(matches!(Record::from_str(line), Err(Error::ParseFloat(_))));
+
+        let (record, errors) = Record::from_str_lenient(line).unwrap();
+        let Record::Update(_, properties) = record else {
+            panic!("{record:?}");
+        };
+        assert_eq!(
+            properties[0],
+            ObjectProperty::T(Coords {
+                longitude: Some(1.0),
+                latitude: None,
+                altitude: Some(3.0),
+                roll: Some(4.0),
+                pitch: Some(5.0),
+                yaw: Some(6.0),
+                ..Default::default()
+            })
+        );
+        assert_eq!(properties.len(), 2);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "T=1|x|3|4|5|6");
+    }
+
+    /// `(ACMI line, serde JSON)`. The serde representation is a stable
+    /// contract, any change here must be deliberate.
+    const SERDE_GOLDEN: &[(&str, &str)] = &[
+        ("-2D50A7", r#"{"type":"remove","value":2969767}"#),
+        ("#12.5", r#"{"type":"frame","value":12.5}"#),
+        (
+            "0,Event=Message|705|Maverick has violated ATC directives",
+            r#"{"type":"event","value":{"type":"message","value":[1797,"Maverick has violated ATC directives"]}}"#,
+        ),
+        (
+            "0,Event=Timeout|SourceId:507|AmmoType:FOX2|AmmoCount:1|Bullseye:50/15000/2500|TargetId:201|IntendedTarget:Leader|Outcome:Kill",
+            r#"{"type":"event","value":{"type":"timeout","value":{"sourceId":"507","ammoType":"FOX2","ammoCount":"1","bullseye":"50/15000/2500","targetId":"201","intendedTarget":"Leader","outcome":"Kill"}}}"#,
+        ),
+        (
+            "0,ReferenceTime=2011-06-02T05:00:00Z,Title=Counter Attack",
+            r#"{"type":"globalProperties","value":[{"type":"referenceTime","value":"2011-06-02T05:00:00Z"},{"type":"title","value":"Counter Attack"}]}"#,
+        ),
+        (
+            "A,T=1|2|3|4|5|6|7|8|9,Type=Air,Color=Red,Disabled=1,Unknown=x",
+            r#"{"type":"update","value":[10,[{"type":"t","value":{"longitude":1.0,"latitude":2.0,"altitude":3.0,"roll":4.0,"pitch":5.0,"yaw":6.0,"u":7.0,"v":8.0,"heading":9.0}},{"type":"type","value":["Air"]},{"type":"color","value":"Red"},{"type":"disabled","value":true},{"type":"unknown","value":["Unknown","x"]}]]}"#,
+        ),
+        (
+            "B,T=||300",
+            r#"{"type":"update","value":[11,[{"type":"t","value":{"longitude":null,"latitude":null,"altitude":300.0,"roll":null,"pitch":null,"yaw":null,"u":null,"v":null,"heading":null}}]]}"#,
+        ),
+    ];
+
+    #[test]
+    fn test_serde_golden() {
+        for (line, json) in SERDE_GOLDEN {
+            let record = Record::from_str(line).unwrap();
+            assert_eq!(&serde_json::to_string(&record).unwrap(), json, "{line}");
+            assert_eq!(
+                serde_json::from_str::<Record>(json).unwrap(),
+                record,
+                "{line}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_kind() {
+        for (line, kind) in [
+            ("-A", RecordKind::Remove),
+            ("#1", RecordKind::Frame),
+            ("0,Event=Bookmark|x", RecordKind::Event),
+            ("0,Title=x", RecordKind::GlobalProperties),
+            ("A,Name=x", RecordKind::Update),
+        ] {
+            assert_eq!(Record::from_str(line).unwrap().kind(), kind, "{line}");
+        }
+    }
+
+    #[test]
+    fn test_unknown_value() {
+        let record = Record::from_str("A,Temperature=21.5,Count=3,Squadron=VF-84").unwrap();
+        let Record::Update(_, properties) = record else {
+            panic!("{record:?}");
+        };
+        let values: Vec<_> = properties
+            .iter()
+            .map(|property| match property {
+                ObjectProperty::Unknown(_, value) => value,
+                property => panic!("{property:?}"),
+            })
+            .collect();
+        assert_eq!(values[0].as_f64(), Some(21.5));
+        assert_eq!(values[0].as_u64(), None);
+        assert_eq!(values[1].as_u64(), Some(3));
+        assert_eq!(values[1].as_f64(), Some(3.0));
+        assert_eq!(values[2].as_f64(), None);
+        assert_eq!(values[2].as_str(), "VF-84");
+
+        let record = Record::from_str("0,Weather=12").unwrap();
+        assert_eq!(
+            record,
+            Record::GlobalProperties(vec![GlobalProperty::Unknown(
+                "Weather".to_string(),
+                "12".into()
+            )])
+        );
+    }
+
+    #[test]
+    fn test_format_object_id() {
+        for id in [0, 1, 0xA, 0x2D50A7, u64::MAX] {
+            assert_eq!(parse_object_id(&format_object_id(id)).unwrap(), id);
+        }
+        assert_eq!(format_object_id(0x2D50A7), "2D50A7");
+        assert_eq!(
+            Record::Remove(0x2D50A7).to_string(),
+            format!("-{}", format_object_id(0x2D50A7))
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(Record::from_str("A,T=-129|43|300")
+            .unwrap()
+            .validate()
+            .is_empty());
+        assert_eq!(
+            Record::Update(0, vec![]).validate(),
+            vec![Warning::ZeroObjectId]
+        );
+        assert_eq!(Record::Remove(0).validate(), vec![Warning::ZeroObjectId]);
+        assert_eq!(
+            Record::from_str("A,T=1|95|300").unwrap().validate(),
+            vec![Warning::CoordsOutOfRange {
+                id: 0xA,
+                coords: Coords::from_str("1|95|300").unwrap(),
+            }]
+        );
+        assert_eq!(Record::from_str("A,T=-181||").unwrap().validate().len(), 1);
+    }
+
+    #[test]
+    fn test_approx_eq() {
+        let a = Record::from_str("A,T=-129.5|43.25|300,HDG=90,Name=F-16").unwrap();
+        let b =
+            Record::from_str("A,T=-129.5000001|43.2499999|300.0000004,HDG=90.0000001,Name=F-16")
+                .unwrap();
+        assert_ne!(a, b);
+        assert!(a.approx_eq(&b, 1e-6));
+        assert!(!a.approx_eq(&b, 1e-8));
+
+        let c = Record::from_str("A,T=-129.5|43.25|,HDG=90,Name=F-16").unwrap();
+        assert!(!a.approx_eq(&c, 1.0));
+        let d = Record::from_str("A,T=-129.5|43.25|300,HDG=90,Name=F-15").unwrap();
+        assert!(!a.approx_eq(&d, 1.0));
+
+        assert!(Record::Frame(1.0).approx_eq(&Record::Frame(1.0 + 1e-9), 1e-6));
+        assert!(Record::from_str("0,ReferenceLongitude=-129.0000001")
+            .unwrap()
+            .approx_eq(
+                &Record::from_str("0,ReferenceLongitude=-129").unwrap(),
+                1e-6
+            ));
+        assert!(!Record::Remove(1).approx_eq(&Record::Remove(2), 1.0));
+    }
+
+    #[test]
+    fn test_values_containing_equals() {
+        let record = Record::from_str(
+            "A,Label=x=y,Squawk=7=7,Group=a==b,Pilot==Viper,Debug=k=v=,FooBar=1=2",
+        )
+        .unwrap();
+        assert_eq!(
+            record,
+            Record::Update(
+                0xA,
+                vec![
+                    ObjectProperty::Label("x=y".to_string()),
+                    ObjectProperty::Squawk("7=7".to_string()),
+                    ObjectProperty::Group("a==b".into()),
+                    ObjectProperty::Pilot("=Viper".into()),
+                    ObjectProperty::Debug("k=v=".to_string()),
+                    ObjectProperty::Unknown("FooBar".to_string(), "1=2".into()),
+                ]
+            )
+        );
+        assert_eq!(
+            record.to_string(),
+            "A,Label=x=y,Squawk=7=7,Group=a==b,Pilot==Viper,Debug=k=v=,FooBar=1=2"
+        );
+
+        let record = Record::from_str("0,Comments=a=b,Foo=c=d").unwrap();
+        assert_eq!(
+            record,
+            Record::GlobalProperties(vec![
+                GlobalProperty::Comments("a=b".to_string()),
+                GlobalProperty::Unknown("Foo".to_string(), "c=d".into()),
+            ])
+        );
+        assert_eq!(record.to_string(), "0,Comments=a=b,Foo=c=d");
+    }
+
+    #[test]
+    fn test_accept() {
+        #[derive(Default)]
+        struct Counter {
+            frames: usize,
+            properties: usize,
+            removed: Vec<u64>,
+        }
+
+        impl RecordVisitor for Counter {
+            fn on_frame(&mut self, _time: f64) {
+                self.frames += 1;
+            }
+
+            fn on_update(&mut self, _id: u64, properties: &[ObjectProperty]) {
+                self.properties += properties.len();
+            }
+
+            fn on_remove(&mut self, id: u64) {
+                self.removed.push(id);
+            }
+        }
+
+        let mut counter = Counter::default();
+        for line in [
+            "0,Title=A",
+            "#0",
+            "A,T=1|2|3,Name=F-16C-52",
+            "#1",
+            "B,HDG=1",
+            "0,Event=Destroyed|A|",
+            "-A",
+        ] {
+            Record::from_str(line).unwrap().accept(&mut counter);
+        }
+        assert_eq!(counter.frames, 2);
+        assert_eq!(counter.properties, 3);
+        assert_eq!(counter.removed, [0xA]);
+    }
+
+    #[test]
+    fn test_from_str_ignoring() {
+        let line = "A,T=1|2|3,RadarMode=1,RadarRange=1000,FuelWeight=x,Name=F-16C-52";
+        assert_eq!(
+            Record::from_str_ignoring(line, &["Radar", "Fuel"]).unwrap(),
+            Record::Update(
+                0xA,
+                vec![
+                    ObjectProperty::T(Coords::position(1.0, 2.0, 3.0)),
+                    ObjectProperty::Name("F-16C-52".into()),
+                ]
+            )
+        );
+        assert!(Record::from_str(line).is_err());
+    }
+
+    #[test]
+    fn test_from_str_rejecting_duplicates() {
+        let line = "A,Name=F-16C-52,HDG=10,Name=F-15C";
+        assert_eq!(
+            Record::from_str(line).unwrap(),
+            Record::Update(
+                0xA,
+                vec![
+                    ObjectProperty::Name("F-16C-52".into()),
+                    ObjectProperty::Hdg(10.0),
+                    ObjectProperty::Name("F-15C".into()),
+                ]
+            )
+        );
+        assert!(matches!(
+            Record::from_str_rejecting_duplicates(line),
+            Err(Error::DuplicateProperty(_))
+        ));
+
+        // unknown properties are told apart by name
+        let line = "A,Foo=1,Bar=2,HDG=10";
+        assert_eq!(
+            Record::from_str_rejecting_duplicates(line).unwrap(),
+            Record::from_str(line).unwrap()
+        );
+        assert!(matches!(
+            Record::from_str_rejecting_duplicates("A,Foo=1,Foo=2"),
+            Err(Error::DuplicateProperty(_))
+        ));
+    }
+
+    #[test]
+    fn test_sanitize() {
+        let line = "A,Name=F-16\u{0}C,Pilot=Vi\u{7}per,HDG=1";
+        let mut record = Record::from_str(line).unwrap();
+        assert_eq!(record.to_string(), line);
+
+        let mut replaced = record.clone();
+        assert!(replaced.sanitize(Sanitize::Replace('?')));
+        assert_eq!(replaced.to_string(), "A,Name=F-16?C,Pilot=Vi?per,HDG=1");
+        assert!(record.sanitize(Sanitize::Strip));
+        assert_eq!(record.to_string(), "A,Name=F-16C,Pilot=Viper,HDG=1");
+        assert!(!record.sanitize(Sanitize::Strip));
+
+        let mut record = Record::Update(0xA, vec![ObjectProperty::Label("a\nb".to_string())]);
+        assert!(!record.sanitize(Sanitize::Strip));
+
+        let mut record = Record::from_str("0,Title=\u{1b}[31mA,Foo=\u{0}").unwrap();
+        assert!(record.sanitize(Sanitize::Strip));
+        assert_eq!(record.to_string(), "0,Title=[31mA,Foo=");
+    }
 }
@@ -1,11 +1,35 @@
-use std::{collections::HashSet, str::FromStr};
+use std::{collections::HashSet, fmt, str::FromStr, sync::Arc};
 
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
 use crate::error::Error;
 
-use super::parse_object_id;
+use crate::acmi::state::ObjectState;
+
+use super::{approx_eq_f64, parse_bool, parse_object_id, PropertyKey, UnknownValue};
+
+/// Mean radius of the Earth, in meters.
+const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// Wraps a heading in degrees into `[0, 360)`.
+pub fn normalize_heading(degrees: f64) -> f64 {
+    degrees.rem_euclid(360.0)
+}
+
+/// Wraps a relative angle in degrees, like an azimuth or a roll, into
+/// `[-180, 180)`.
+pub fn normalize_azimuth(degrees: f64) -> f64 {
+    (degrees + 180.0).rem_euclid(360.0) - 180.0
+}
+
+pub fn meters_to_nautical_miles(meters: f64) -> f64 {
+    meters / 1852.0
+}
+
+pub fn meters_to_feet(meters: f64) -> f64 {
+    meters / 0.3048
+}
 
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase", tag = "type", content = "value")]
@@ -23,7 +47,7 @@ pub enum ObjectProperty {
     /// only properties which *CANNOT* be predefined in Tacview
     /// [database](https://www.tacview.net/documentation/database/en/).  
     /// `Name=F-16C-52`
-    Name(String),
+    Name(Arc<str>),
     /// Object types are built using tags. This makes object management much
     /// more powerful and transparent than with the previous exclusive types.
     /// (see below for the list of supported types). `Type` and `Name` are the
@@ -57,17 +81,17 @@ pub enum ObjectProperty {
     Icao24(String),
     /// Aircraft pilot in command name.  
     /// `Pilot=Iceman`
-    Pilot(String),
+    Pilot(Arc<str>),
     /// Group the object belongs to. Used to group objects together. For
     /// example, a formation of F-16 flying a CAP together.  
     /// `Group=Springfield`
-    Group(String),
+    Group(Arc<str>),
     /// ISO 3166-1 alpha-2 country code.  
     /// `Country=us`
-    Country(String),
+    Country(Arc<str>),
     /// Coalition  
     /// `Coalition=Allies`
-    Coalition(String),
+    Coalition(Arc<str>),
     /// Can be one of the following: `Red`, `Orange`, `Yellow` (Tacview 1.8.8),
     /// `Green`, `Cyan` (Tacview 1.8.8), `Blue`, `Violet`. Colors are predefined
     /// to ensure a clear display of the whole battlefield in all conditions.  
@@ -431,7 +455,342 @@ pub enum ObjectProperty {
     SpO2(f64),
 
     /// Unknown property. `(name, value)`
-    Unknown(String, String),
+    Unknown(String, UnknownValue),
+}
+
+impl ObjectProperty {
+    /// Key of the property, e.g. `T` or `Name`.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::T(_) => "T",
+            Self::Name(_) => "Name",
+            Self::Type(_) => "Type",
+            Self::Parent(_) => "Parent",
+            Self::Next(_) => "Next",
+            Self::Callsign(_) => "CallSign",
+            Self::Registration(_) => "Registration",
+            Self::Squawk(_) => "Squawk",
+            Self::Icao24(_) => "ICAO24",
+            Self::Pilot(_) => "Pilot",
+            Self::Group(_) => "Group",
+            Self::Country(_) => "Country",
+            Self::Coalition(_) => "Coalition",
+            Self::Color(_) => "Color",
+            Self::Shape(_) => "Shape",
+            Self::Debug(_) => "Debug",
+            Self::Label(_) => "Label",
+            Self::FocusedTarget(_) => "FocusedTarget",
+            Self::LockedTarget(_) => "LockedTarget",
+            Self::LockedTarget2(_) => "LockedTarget2",
+            Self::LockedTarget3(_) => "LockedTarget3",
+            Self::LockedTarget4(_) => "LockedTarget4",
+            Self::LockedTarget5(_) => "LockedTarget5",
+            Self::LockedTarget6(_) => "LockedTarget6",
+            Self::LockedTarget7(_) => "LockedTarget7",
+            Self::LockedTarget8(_) => "LockedTarget8",
+            Self::LockedTarget9(_) => "LockedTarget9",
+            Self::Importance(_) => "Importance",
+            Self::Slot(_) => "Slot",
+            Self::Disabled(_) => "Disabled",
+            Self::Visible(_) => "Visible",
+            Self::Health(_) => "Health",
+            Self::Length(_) => "Length",
+            Self::Width(_) => "Width",
+            Self::Radius(_) => "Radius",
+            Self::Ias(_) => "IAS",
+            Self::Cas(_) => "CAS",
+            Self::Tas(_) => "TAS",
+            Self::Mach(_) => "Mach",
+            Self::Aoa(_) => "AOA",
+            Self::Aos(_) => "AOS",
+            Self::Agl(_) => "AGL",
+            Self::Hdg(_) => "HDG",
+            Self::Hdm(_) => "HDM",
+            Self::Throttle(_) => "Throttle",
+            Self::Afterburner(_) => "Afterburner",
+            Self::AirBrakes(_) => "AirBrakes",
+            Self::Flaps(_) => "Flaps",
+            Self::LandingGear(_) => "LandingGear",
+            Self::LandingGearHandle(_) => "LandingGearHandle",
+            Self::Tailhook(_) => "Tailhook",
+            Self::Parachute(_) => "Parachute",
+            Self::DragChute(_) => "DragChute",
+            Self::FuelWeight(_) => "FuelWeight",
+            Self::FuelWeight2(_) => "FuelWeight2",
+            Self::FuelWeight3(_) => "FuelWeight3",
+            Self::FuelWeight4(_) => "FuelWeight4",
+            Self::FuelWeight5(_) => "FuelWeight5",
+            Self::FuelWeight6(_) => "FuelWeight6",
+            Self::FuelWeight7(_) => "FuelWeight7",
+            Self::FuelWeight8(_) => "FuelWeight8",
+            Self::FuelWeight9(_) => "FuelWeight9",
+            Self::FuelVolume(_) => "FuelVolume",
+            Self::FuelVolume2(_) => "FuelVolume2",
+            Self::FuelVolume3(_) => "FuelVolume3",
+            Self::FuelVolume4(_) => "FuelVolume4",
+            Self::FuelVolume5(_) => "FuelVolume5",
+            Self::FuelVolume6(_) => "FuelVolume6",
+            Self::FuelVolume7(_) => "FuelVolume7",
+            Self::FuelVolume8(_) => "FuelVolume8",
+            Self::FuelVolume9(_) => "FuelVolume9",
+            Self::FuelFlowWeight(_) => "FuelFlowWeight",
+            Self::FuelFlowWeight2(_) => "FuelFlowWeight2",
+            Self::FuelFlowWeight3(_) => "FuelFlowWeight3",
+            Self::FuelFlowWeight4(_) => "FuelFlowWeight4",
+            Self::FuelFlowWeight5(_) => "FuelFlowWeight5",
+            Self::FuelFlowWeight6(_) => "FuelFlowWeight6",
+            Self::FuelFlowWeight7(_) => "FuelFlowWeight7",
+            Self::FuelFlowVolume(_) => "FuelFlowVolume",
+            Self::FuelFlowVolume2(_) => "FuelFlowVolume2",
+            Self::FuelFlowVolume3(_) => "FuelFlowVolume3",
+            Self::FuelFlowVolume4(_) => "FuelFlowVolume4",
+            Self::FuelFlowVolume5(_) => "FuelFlowVolume5",
+            Self::FuelFlowVolume6(_) => "FuelFlowVolume6",
+            Self::FuelFlowVolume7(_) => "FuelFlowVolume7",
+            Self::RadarMode(_) => "RadarMode",
+            Self::RadarAzimuth(_) => "RadarAzimuth",
+            Self::RadarElevation(_) => "RadarElevation",
+            Self::RadarRoll(_) => "RadarRoll",
+            Self::RadarRange(_) => "RadarRange",
+            Self::RadarHorizontalBeamwidth(_) => "RadarHorizontalBeamwidth",
+            Self::RadarVerticalBeamwidth(_) => "RadarVerticalBeamwidth",
+            Self::RadarRangeGateAzimuth(_) => "RadarRangeGateAzimuth",
+            Self::RadarRangeGateElevation(_) => "RadarRangeGateElevation",
+            Self::RadarRangeGateRoll(_) => "RadarRangeGateRoll",
+            Self::RadarRangeGateMin(_) => "RadarRangeGateMin",
+            Self::RadarRangeGateMax(_) => "RadarRangeGateMax",
+            Self::RadarRangeGateHorizontalBeamwidth(_) => "RadarRangeGateHorizontalBeamwidth",
+            Self::RadarRangeGateVerticalBeamwidth(_) => "RadarRangeGateVerticalBeamwidth",
+            Self::LockedTargetMode(_) => "LockedTargetMode",
+            Self::LockedTargetAzimuth(_) => "LockedTargetAzimuth",
+            Self::LockedTargetElevation(_) => "LockedTargetElevation",
+            Self::LockedTargetRange(_) => "LockedTargetRange",
+            Self::EngagementMode(_) => "EngagementMode",
+            Self::EngagementMode2(_) => "EngagementMode2",
+            Self::EngagementRange(_) => "EngagementRange",
+            Self::EngagementRange2(_) => "EngagementRange2",
+            Self::VerticalEngagementRange(_) => "VerticalEngagementRange",
+            Self::VerticalEngagementRange2(_) => "VerticalEngagementRange2",
+            Self::RollControlInput(_) => "RollControlInput",
+            Self::PitchControlInput(_) => "PitchControlInput",
+            Self::YawControlInput(_) => "YawControlInput",
+            Self::RollControlPosition(_) => "RollControlPosition",
+            Self::PitchControlPosition(_) => "PitchControlPosition",
+            Self::YawControlPosition(_) => "YawControlPosition",
+            Self::RollTrimTab(_) => "RollTrimTab",
+            Self::PitchTrimTab(_) => "PitchTrimTab",
+            Self::YawTrimTab(_) => "YawTrimTab",
+            Self::AileronLeft(_) => "AileronLeft",
+            Self::AileronRight(_) => "AileronRight",
+            Self::Elevator(_) => "Elevator",
+            Self::Rudder(_) => "Rudder",
+            Self::PilotHeadRoll(_) => "PilotHeadRoll",
+            Self::PilotHeadPitch(_) => "PilotHeadPitch",
+            Self::PilotHeadYaw(_) => "PilotHeadYaw",
+            Self::VerticalGForce(_) => "VerticalGForce",
+            Self::LongitudinalGForce(_) => "LongitudinalGForce",
+            Self::LateralGForce(_) => "LateralGForce",
+            Self::TriggerPressed(_) => "TriggerPressed",
+            Self::Enl(_) => "ENL",
+            Self::HeartRate(_) => "HeartRate",
+            Self::SpO2(_) => "SpO2",
+            Self::Unknown(name, _) => name,
+        }
+    }
+
+    /// Same as `==`, with floats, including coordinates, compared within
+    /// `epsilon`.
+    pub fn approx_eq(&self, other: &Self, epsilon: f64) -> bool {
+        match (self, other) {
+            (Self::T(a), Self::T(b)) => a.approx_eq(b, epsilon),
+            _ if std::mem::discriminant(self) == std::mem::discriminant(other) => {
+                match (self.as_f64(), other.as_f64()) {
+                    (Some(a), Some(b)) => approx_eq_f64(a, b, epsilon),
+                    _ => self == other,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// The id of the object this property refers to, like a `Parent` or a
+    /// `LockedTarget`.
+    pub(crate) fn object_id_mut(&mut self) -> Option<&mut u64> {
+        match self {
+            Self::Parent(id)
+            | Self::Next(id)
+            | Self::FocusedTarget(id)
+            | Self::LockedTarget(id)
+            | Self::LockedTarget2(id)
+            | Self::LockedTarget3(id)
+            | Self::LockedTarget4(id)
+            | Self::LockedTarget5(id)
+            | Self::LockedTarget6(id)
+            | Self::LockedTarget7(id)
+            | Self::LockedTarget8(id)
+            | Self::LockedTarget9(id) => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Key of the property in an [`ObjectState`],
+    /// distinguishing unknown properties by name.
+    pub fn key(&self) -> PropertyKey<Self> {
+        match self {
+            Self::Unknown(name, _) => PropertyKey::Unknown(name.clone()),
+            property => PropertyKey::Known(std::mem::discriminant(property)),
+        }
+    }
+
+    /// Text value of the property, if it has one.
+    pub(crate) fn text_mut(&mut self) -> Option<&mut String> {
+        match self {
+            Self::Callsign(value)
+            | Self::Registration(value)
+            | Self::Squawk(value)
+            | Self::Icao24(value)
+            | Self::Shape(value)
+            | Self::Debug(value)
+            | Self::Label(value)
+            | Self::Unknown(_, UnknownValue(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Text value of the property, if it is one of those shared between
+    /// records, see [`Interner`](super::Interner).
+    pub(crate) fn shared_text_mut(&mut self) -> Option<&mut Arc<str>> {
+        match self {
+            Self::Name(value)
+            | Self::Pilot(value)
+            | Self::Group(value)
+            | Self::Country(value)
+            | Self::Coalition(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Wraps headings with [`normalize_heading`], and azimuths and rolls
+    /// with [`normalize_azimuth`]. Parsed values are kept as received.
+    pub fn normalized(self) -> Self {
+        match self {
+            Self::T(coords) => Self::T(coords.normalized()),
+            Self::Hdg(value) => Self::Hdg(normalize_heading(value)),
+            Self::RadarAzimuth(value) => Self::RadarAzimuth(normalize_azimuth(value)),
+            Self::RadarRoll(value) => Self::RadarRoll(normalize_azimuth(value)),
+            Self::RadarRangeGateAzimuth(value) => {
+                Self::RadarRangeGateAzimuth(normalize_azimuth(value))
+            }
+            Self::RadarRangeGateRoll(value) => Self::RadarRangeGateRoll(normalize_azimuth(value)),
+            Self::LockedTargetAzimuth(value) => Self::LockedTargetAzimuth(normalize_azimuth(value)),
+            Self::PilotHeadRoll(value) => Self::PilotHeadRoll(normalize_azimuth(value)),
+            Self::PilotHeadYaw(value) => Self::PilotHeadYaw(normalize_azimuth(value)),
+            property => property,
+        }
+    }
+
+    /// Value of the properties holding a float.
+    pub(crate) fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Visible(value)
+            | Self::Health(value)
+            | Self::Length(value)
+            | Self::Width(value)
+            | Self::Radius(value)
+            | Self::Ias(value)
+            | Self::Cas(value)
+            | Self::Tas(value)
+            | Self::Mach(value)
+            | Self::Aoa(value)
+            | Self::Aos(value)
+            | Self::Agl(value)
+            | Self::Hdg(value)
+            | Self::Hdm(value)
+            | Self::Throttle(value)
+            | Self::Afterburner(value)
+            | Self::AirBrakes(value)
+            | Self::Flaps(value)
+            | Self::LandingGear(value)
+            | Self::LandingGearHandle(value)
+            | Self::Tailhook(value)
+            | Self::Parachute(value)
+            | Self::DragChute(value)
+            | Self::FuelWeight(value)
+            | Self::FuelWeight2(value)
+            | Self::FuelWeight3(value)
+            | Self::FuelWeight4(value)
+            | Self::FuelWeight5(value)
+            | Self::FuelWeight6(value)
+            | Self::FuelWeight7(value)
+            | Self::FuelWeight8(value)
+            | Self::FuelWeight9(value)
+            | Self::FuelVolume(value)
+            | Self::FuelVolume2(value)
+            | Self::FuelVolume3(value)
+            | Self::FuelVolume4(value)
+            | Self::FuelVolume5(value)
+            | Self::FuelVolume6(value)
+            | Self::FuelVolume7(value)
+            | Self::FuelVolume8(value)
+            | Self::FuelVolume9(value)
+            | Self::FuelFlowWeight(value)
+            | Self::FuelFlowWeight2(value)
+            | Self::FuelFlowWeight3(value)
+            | Self::FuelFlowWeight4(value)
+            | Self::FuelFlowWeight5(value)
+            | Self::FuelFlowWeight6(value)
+            | Self::FuelFlowWeight7(value)
+            | Self::FuelFlowVolume(value)
+            | Self::FuelFlowVolume2(value)
+            | Self::FuelFlowVolume3(value)
+            | Self::FuelFlowVolume4(value)
+            | Self::FuelFlowVolume5(value)
+            | Self::FuelFlowVolume6(value)
+            | Self::FuelFlowVolume7(value)
+            | Self::RadarAzimuth(value)
+            | Self::RadarElevation(value)
+            | Self::RadarRoll(value)
+            | Self::RadarRange(value)
+            | Self::RadarHorizontalBeamwidth(value)
+            | Self::RadarVerticalBeamwidth(value)
+            | Self::RadarRangeGateAzimuth(value)
+            | Self::RadarRangeGateElevation(value)
+            | Self::RadarRangeGateRoll(value)
+            | Self::RadarRangeGateMin(value)
+            | Self::RadarRangeGateMax(value)
+            | Self::RadarRangeGateHorizontalBeamwidth(value)
+            | Self::RadarRangeGateVerticalBeamwidth(value)
+            | Self::LockedTargetAzimuth(value)
+            | Self::LockedTargetElevation(value)
+            | Self::LockedTargetRange(value)
+            | Self::EngagementRange(value)
+            | Self::EngagementRange2(value)
+            | Self::VerticalEngagementRange(value)
+            | Self::VerticalEngagementRange2(value)
+            | Self::RollControlInput(value)
+            | Self::PitchControlInput(value)
+            | Self::YawControlInput(value)
+            | Self::RollControlPosition(value)
+            | Self::PitchControlPosition(value)
+            | Self::YawControlPosition(value)
+            | Self::RollTrimTab(value)
+            | Self::PitchTrimTab(value)
+            | Self::YawTrimTab(value)
+            | Self::AileronLeft(value)
+            | Self::AileronRight(value)
+            | Self::Elevator(value)
+            | Self::Rudder(value)
+            | Self::PilotHeadRoll(value)
+            | Self::PilotHeadPitch(value)
+            | Self::PilotHeadYaw(value)
+            | Self::VerticalGForce(value)
+            | Self::LongitudinalGForce(value)
+            | Self::LateralGForce(value)
+            | Self::Enl(value)
+            | Self::SpO2(value) => Some(*value),
+            _ => None,
+        }
+    }
 }
 
 impl FromStr for ObjectProperty {
@@ -442,7 +801,7 @@ impl FromStr for ObjectProperty {
             let coords = Coords::from_str(value)?;
             Ok(Self::T(coords))
         } else if let Some(value) = s.strip_prefix("Name=") {
-            Ok(Self::Name(value.to_string()))
+            Ok(Self::Name(value.into()))
         } else if let Some(value) = s.strip_prefix("Type=") {
             let tags = value.split('+').map(Tag::from_str).try_collect()?;
             Ok(Self::Type(tags))
@@ -452,7 +811,10 @@ impl FromStr for ObjectProperty {
         } else if let Some(value) = s.strip_prefix("Next=") {
             let id = parse_object_id(value)?;
             Ok(Self::Next(id))
-        } else if let Some(value) = s.strip_prefix("Callsign=") {
+        } else if let Some(value) = s
+            .strip_prefix("CallSign=")
+            .or_else(|| s.strip_prefix("Callsign="))
+        {
             Ok(Self::Callsign(value.to_string()))
         } else if let Some(value) = s.strip_prefix("Registration=") {
             Ok(Self::Registration(value.to_string()))
@@ -461,13 +823,13 @@ impl FromStr for ObjectProperty {
         } else if let Some(value) = s.strip_prefix("ICAO24=") {
             Ok(Self::Icao24(value.to_string()))
         } else if let Some(value) = s.strip_prefix("Pilot=") {
-            Ok(Self::Pilot(value.to_string()))
+            Ok(Self::Pilot(value.into()))
         } else if let Some(value) = s.strip_prefix("Group=") {
-            Ok(Self::Group(value.to_string()))
+            Ok(Self::Group(value.into()))
         } else if let Some(value) = s.strip_prefix("Country=") {
-            Ok(Self::Country(value.to_string()))
+            Ok(Self::Country(value.into()))
         } else if let Some(value) = s.strip_prefix("Coalition=") {
-            Ok(Self::Coalition(value.to_string()))
+            Ok(Self::Coalition(value.into()))
         } else if let Some(value) = s.strip_prefix("Color=") {
             let color = Color::from_str(value)?;
             Ok(Self::Color(color))
@@ -514,8 +876,7 @@ impl FromStr for ObjectProperty {
             let value = u64::from_str(value).map_err(Error::ParseInt)?;
             Ok(Self::Slot(value))
         } else if let Some(value) = s.strip_prefix("Disabled=") {
-            let value = value == "1";
-            Ok(Self::Disabled(value))
+            Ok(Self::Disabled(parse_bool("Disabled", value)))
         } else if let Some(value) = s.strip_prefix("Visible=") {
             let value = f64::from_str(value).map_err(Error::ParseFloat)?;
             Ok(Self::Visible(value))
@@ -726,6 +1087,9 @@ impl FromStr for ObjectProperty {
         } else if let Some(value) = s.strip_prefix("LockedTargetMode=") {
             let value = u64::from_str(value).map_err(Error::ParseInt)?;
             Ok(Self::LockedTargetMode(value))
+        } else if let Some(value) = s.strip_prefix("LockedTargetAzimuth=") {
+            let value = f64::from_str(value).map_err(Error::ParseFloat)?;
+            Ok(Self::LockedTargetAzimuth(value))
         } else if let Some(value) = s.strip_prefix("LockedTargetElevation=") {
             let value = f64::from_str(value).map_err(Error::ParseFloat)?;
             Ok(Self::LockedTargetElevation(value))
@@ -808,8 +1172,7 @@ impl FromStr for ObjectProperty {
             let value = f64::from_str(value).map_err(Error::ParseFloat)?;
             Ok(Self::LateralGForce(value))
         } else if let Some(value) = s.strip_prefix("TriggerPressed=") {
-            let value = value == "1" || value == "1.0";
-            Ok(Self::TriggerPressed(value))
+            Ok(Self::TriggerPressed(parse_bool("TriggerPressed", value)))
         } else if let Some(value) = s.strip_prefix("ENL=") {
             let value = f64::from_str(value).map_err(Error::ParseFloat)?;
             Ok(Self::Enl(value))
@@ -823,11 +1186,170 @@ impl FromStr for ObjectProperty {
             let (name, value) = s
                 .split_once('=')
                 .ok_or_else(|| Error::MalformedObjectProperty(s.to_string()))?;
-            Ok(Self::Unknown(name.to_string(), value.to_string()))
+            Ok(Self::Unknown(name.to_string(), value.into()))
         }
     }
 }
 
+impl fmt::Display for ObjectProperty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::T(coords) => write!(f, "T={coords}"),
+            Self::Name(value) => write!(f, "Name={value}"),
+            Self::Type(tags) => {
+                // sorted to keep the output stable
+                let tags = tags.iter().map(Tag::to_string).sorted().join("+");
+                write!(f, "Type={tags}")
+            }
+            Self::Parent(id) => write!(f, "Parent={id:X}"),
+            Self::Next(id) => write!(f, "Next={id:X}"),
+            Self::Callsign(value) => write!(f, "CallSign={value}"),
+            Self::Registration(value) => write!(f, "Registration={value}"),
+            Self::Squawk(value) => write!(f, "Squawk={value}"),
+            Self::Icao24(value) => write!(f, "ICAO24={value}"),
+            Self::Pilot(value) => write!(f, "Pilot={value}"),
+            Self::Group(value) => write!(f, "Group={value}"),
+            Self::Country(value) => write!(f, "Country={value}"),
+            Self::Coalition(value) => write!(f, "Coalition={value}"),
+            Self::Color(value) => write!(f, "Color={value}"),
+            Self::Shape(value) => write!(f, "Shape={value}"),
+            Self::Debug(value) => write!(f, "Debug={value}"),
+            Self::Label(value) => write!(f, "Label={value}"),
+            Self::FocusedTarget(id) => write!(f, "FocusedTarget={id:X}"),
+            Self::LockedTarget(id) => write!(f, "LockedTarget={id:X}"),
+            Self::LockedTarget2(id) => write!(f, "LockedTarget2={id:X}"),
+            Self::LockedTarget3(id) => write!(f, "LockedTarget3={id:X}"),
+            Self::LockedTarget4(id) => write!(f, "LockedTarget4={id:X}"),
+            Self::LockedTarget5(id) => write!(f, "LockedTarget5={id:X}"),
+            Self::LockedTarget6(id) => write!(f, "LockedTarget6={id:X}"),
+            Self::LockedTarget7(id) => write!(f, "LockedTarget7={id:X}"),
+            Self::LockedTarget8(id) => write!(f, "LockedTarget8={id:X}"),
+            Self::LockedTarget9(id) => write!(f, "LockedTarget9={id:X}"),
+            Self::Importance(value) => write!(f, "Importance={value}"),
+            Self::Slot(value) => write!(f, "Slot={value}"),
+            Self::Disabled(value) => write!(f, "Disabled={}", u8::from(*value)),
+            Self::Visible(value) => write!(f, "Visible={value}"),
+            Self::Health(value) => write!(f, "Health={value}"),
+            Self::Length(value) => write!(f, "Length={value}"),
+            Self::Width(value) => write!(f, "Width={value}"),
+            Self::Radius(value) => write!(f, "Radius={value}"),
+            Self::Ias(value) => write!(f, "IAS={value}"),
+            Self::Cas(value) => write!(f, "CAS={value}"),
+            Self::Tas(value) => write!(f, "TAS={value}"),
+            Self::Mach(value) => write!(f, "Mach={value}"),
+            Self::Aoa(value) => write!(f, "AOA={value}"),
+            Self::Aos(value) => write!(f, "AOS={value}"),
+            Self::Agl(value) => write!(f, "AGL={value}"),
+            Self::Hdg(value) => write!(f, "HDG={value}"),
+            Self::Hdm(value) => write!(f, "HDM={value}"),
+            Self::Throttle(value) => write!(f, "Throttle={value}"),
+            Self::Afterburner(value) => write!(f, "Afterburner={value}"),
+            Self::AirBrakes(value) => write!(f, "AirBrakes={value}"),
+            Self::Flaps(value) => write!(f, "Flaps={value}"),
+            Self::LandingGear(value) => write!(f, "LandingGear={value}"),
+            Self::LandingGearHandle(value) => write!(f, "LandingGearHandle={value}"),
+            Self::Tailhook(value) => write!(f, "Tailhook={value}"),
+            Self::Parachute(value) => write!(f, "Parachute={value}"),
+            Self::DragChute(value) => write!(f, "DragChute={value}"),
+            Self::FuelWeight(value) => write!(f, "FuelWeight={value}"),
+            Self::FuelWeight2(value) => write!(f, "FuelWeight2={value}"),
+            Self::FuelWeight3(value) => write!(f, "FuelWeight3={value}"),
+            Self::FuelWeight4(value) => write!(f, "FuelWeight4={value}"),
+            Self::FuelWeight5(value) => write!(f, "FuelWeight5={value}"),
+            Self::FuelWeight6(value) => write!(f, "FuelWeight6={value}"),
+            Self::FuelWeight7(value) => write!(f, "FuelWeight7={value}"),
+            Self::FuelWeight8(value) => write!(f, "FuelWeight8={value}"),
+            Self::FuelWeight9(value) => write!(f, "FuelWeight9={value}"),
+            Self::FuelVolume(value) => write!(f, "FuelVolume={value}"),
+            Self::FuelVolume2(value) => write!(f, "FuelVolume2={value}"),
+            Self::FuelVolume3(value) => write!(f, "FuelVolume3={value}"),
+            Self::FuelVolume4(value) => write!(f, "FuelVolume4={value}"),
+            Self::FuelVolume5(value) => write!(f, "FuelVolume5={value}"),
+            Self::FuelVolume6(value) => write!(f, "FuelVolume6={value}"),
+            Self::FuelVolume7(value) => write!(f, "FuelVolume7={value}"),
+            Self::FuelVolume8(value) => write!(f, "FuelVolume8={value}"),
+            Self::FuelVolume9(value) => write!(f, "FuelVolume9={value}"),
+            Self::FuelFlowWeight(value) => write!(f, "FuelFlowWeight={value}"),
+            Self::FuelFlowWeight2(value) => write!(f, "FuelFlowWeight2={value}"),
+            Self::FuelFlowWeight3(value) => write!(f, "FuelFlowWeight3={value}"),
+            Self::FuelFlowWeight4(value) => write!(f, "FuelFlowWeight4={value}"),
+            Self::FuelFlowWeight5(value) => write!(f, "FuelFlowWeight5={value}"),
+            Self::FuelFlowWeight6(value) => write!(f, "FuelFlowWeight6={value}"),
+            Self::FuelFlowWeight7(value) => write!(f, "FuelFlowWeight7={value}"),
+            Self::FuelFlowVolume(value) => write!(f, "FuelFlowVolume={value}"),
+            Self::FuelFlowVolume2(value) => write!(f, "FuelFlowVolume2={value}"),
+            Self::FuelFlowVolume3(value) => write!(f, "FuelFlowVolume3={value}"),
+            Self::FuelFlowVolume4(value) => write!(f, "FuelFlowVolume4={value}"),
+            Self::FuelFlowVolume5(value) => write!(f, "FuelFlowVolume5={value}"),
+            Self::FuelFlowVolume6(value) => write!(f, "FuelFlowVolume6={value}"),
+            Self::FuelFlowVolume7(value) => write!(f, "FuelFlowVolume7={value}"),
+            Self::RadarMode(value) => write!(f, "RadarMode={value}"),
+            Self::RadarAzimuth(value) => write!(f, "RadarAzimuth={value}"),
+            Self::RadarElevation(value) => write!(f, "RadarElevation={value}"),
+            Self::RadarRoll(value) => write!(f, "RadarRoll={value}"),
+            Self::RadarRange(value) => write!(f, "RadarRange={value}"),
+            Self::RadarHorizontalBeamwidth(value) => write!(f, "RadarHorizontalBeamwidth={value}"),
+            Self::RadarVerticalBeamwidth(value) => write!(f, "RadarVerticalBeamwidth={value}"),
+            Self::RadarRangeGateAzimuth(value) => write!(f, "RadarRangeGateAzimuth={value}"),
+            Self::RadarRangeGateElevation(value) => write!(f, "RadarRangeGateElevation={value}"),
+            Self::RadarRangeGateRoll(value) => write!(f, "RadarRangeGateRoll={value}"),
+            Self::RadarRangeGateMin(value) => write!(f, "RadarRangeGateMin={value}"),
+            Self::RadarRangeGateMax(value) => write!(f, "RadarRangeGateMax={value}"),
+            Self::RadarRangeGateHorizontalBeamwidth(value) => {
+                write!(f, "RadarRangeGateHorizontalBeamwidth={value}")
+            }
+            Self::RadarRangeGateVerticalBeamwidth(value) => {
+                write!(f, "RadarRangeGateVerticalBeamwidth={value}")
+            }
+            Self::LockedTargetMode(value) => write!(f, "LockedTargetMode={value}"),
+            Self::LockedTargetAzimuth(value) => write!(f, "LockedTargetAzimuth={value}"),
+            Self::LockedTargetElevation(value) => write!(f, "LockedTargetElevation={value}"),
+            Self::LockedTargetRange(value) => write!(f, "LockedTargetRange={value}"),
+            Self::EngagementMode(value) => write!(f, "EngagementMode={value}"),
+            Self::EngagementMode2(value) => write!(f, "EngagementMode2={value}"),
+            Self::EngagementRange(value) => write!(f, "EngagementRange={value}"),
+            Self::EngagementRange2(value) => write!(f, "EngagementRange2={value}"),
+            Self::VerticalEngagementRange(value) => write!(f, "VerticalEngagementRange={value}"),
+            Self::VerticalEngagementRange2(value) => write!(f, "VerticalEngagementRange2={value}"),
+            Self::RollControlInput(value) => write!(f, "RollControlInput={value}"),
+            Self::PitchControlInput(value) => write!(f, "PitchControlInput={value}"),
+            Self::YawControlInput(value) => write!(f, "YawControlInput={value}"),
+            Self::RollControlPosition(value) => write!(f, "RollControlPosition={value}"),
+            Self::PitchControlPosition(value) => write!(f, "PitchControlPosition={value}"),
+            Self::YawControlPosition(value) => write!(f, "YawControlPosition={value}"),
+            Self::RollTrimTab(value) => write!(f, "RollTrimTab={value}"),
+            Self::PitchTrimTab(value) => write!(f, "PitchTrimTab={value}"),
+            Self::YawTrimTab(value) => write!(f, "YawTrimTab={value}"),
+            Self::AileronLeft(value) => write!(f, "AileronLeft={value}"),
+            Self::AileronRight(value) => write!(f, "AileronRight={value}"),
+            Self::Elevator(value) => write!(f, "Elevator={value}"),
+            Self::Rudder(value) => write!(f, "Rudder={value}"),
+            Self::PilotHeadRoll(value) => write!(f, "PilotHeadRoll={value}"),
+            Self::PilotHeadPitch(value) => write!(f, "PilotHeadPitch={value}"),
+            Self::PilotHeadYaw(value) => write!(f, "PilotHeadYaw={value}"),
+            Self::VerticalGForce(value) => write!(f, "VerticalGForce={value}"),
+            Self::LongitudinalGForce(value) => write!(f, "LongitudinalGForce={value}"),
+            Self::LateralGForce(value) => write!(f, "LateralGForce={value}"),
+            Self::TriggerPressed(value) => write!(f, "TriggerPressed={}", u8::from(*value)),
+            Self::Enl(value) => write!(f, "ENL={value}"),
+            Self::HeartRate(value) => write!(f, "HeartRate={value}"),
+            Self::SpO2(value) => write!(f, "SpO2={value}"),
+            Self::Unknown(name, value) => write!(f, "{name}={value}"),
+        }
+    }
+}
+
+/// Object coordinates. Tacview specifies four layouts:
+///
+/// - `T=Longitude|Latitude|Altitude`
+/// - `T=Longitude|Latitude|Altitude|U|V`
+/// - `T=Longitude|Latitude|Altitude|Roll|Pitch|Yaw`
+/// - `T=Longitude|Latitude|Altitude|Roll|Pitch|Yaw|U|V|Heading`
+///
+/// A non-standard four-field layout is also accepted, its fourth field read as
+/// `roll` by default. Some 2D exporters send
+/// `T=Longitude|Latitude|Altitude|Heading` instead, see
+/// `RealTimeReader::four_field_heading` (`net` feature).
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct Coords {
@@ -843,6 +1365,100 @@ pub struct Coords {
 }
 
 impl Coords {
+    /// Same as `==`, with each component compared within `epsilon`.
+    pub fn approx_eq(&self, other: &Self, epsilon: f64) -> bool {
+        let pairs = [
+            (self.longitude, other.longitude),
+            (self.latitude, other.latitude),
+            (self.altitude, other.altitude),
+            (self.roll, other.roll),
+            (self.pitch, other.pitch),
+            (self.yaw, other.yaw),
+            (self.u, other.u),
+            (self.v, other.v),
+            (self.heading, other.heading),
+        ];
+        pairs.into_iter().all(|pair| match pair {
+            (Some(a), Some(b)) => approx_eq_f64(a, b, epsilon),
+            (a, b) => a.is_none() && b.is_none(),
+        })
+    }
+
+    /// Initial true bearing in degrees `[0, 360)` and great-circle range in
+    /// meters from these coordinates to `other`, on a spherical Earth. Both
+    /// must be absolute, see
+    /// [`World::absolute_coords`](crate::acmi::world::World::absolute_coords):
+    /// offsets from a reference are not distances.
+    pub fn bearing_range_to(&self, other: &Coords) -> Option<(f64, f64)> {
+        let (lon1, lat1) = (self.longitude?.to_radians(), self.latitude?.to_radians());
+        let (lon2, lat2) = (other.longitude?.to_radians(), other.latitude?.to_radians());
+        let (dlon, dlat) = (lon2 - lon1, lat2 - lat1);
+
+        let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+        let range = 2.0 * EARTH_RADIUS * a.sqrt().asin();
+
+        let y = dlon.sin() * lat2.cos();
+        let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * dlon.cos();
+        let bearing = y.atan2(x).to_degrees().rem_euclid(360.0);
+
+        Some((bearing, range))
+    }
+
+    /// Bearing and range of these coordinates from a bullseye object, see
+    /// [`World::bullseye`](crate::acmi::world::World::bullseye). Both are
+    /// relative to `reference`, the `(longitude, latitude)` of
+    /// [`World::reference`](crate::acmi::world::World::reference), and made
+    /// absolute first.
+    pub fn bearing_range_from_bullseye(
+        &self,
+        bullseye: &ObjectState,
+        reference: Option<(f64, f64)>,
+    ) -> Option<(f64, f64)> {
+        let absolute = |coords: &Coords| match reference {
+            Some((longitude, latitude)) => Coords {
+                longitude: coords.longitude.map(|value| value + longitude),
+                latitude: coords.latitude.map(|value| value + latitude),
+                ..coords.clone()
+            },
+            None => coords.clone(),
+        };
+        absolute(bullseye.coords()?).bearing_range_to(&absolute(self))
+    }
+
+    /// Longitude and latitude only, e.g. for ground objects. Written as
+    /// `lon|lat|`.
+    pub fn surface(longitude: f64, latitude: f64) -> Self {
+        Self {
+            longitude: Some(longitude),
+            latitude: Some(latitude),
+            ..Default::default()
+        }
+    }
+
+    /// Position without orientation. Written as `lon|lat|alt`.
+    pub fn position(longitude: f64, latitude: f64, altitude: f64) -> Self {
+        Self {
+            altitude: Some(altitude),
+            ..Self::surface(longitude, latitude)
+        }
+    }
+
+    /// `(latitude, longitude, altitude)`, if all three are known.
+    pub fn lat_lon_alt(&self) -> Option<(f64, f64, f64)> {
+        Some((self.latitude?, self.longitude?, self.altitude?))
+    }
+
+    /// Wraps `yaw` and `heading` into `[0, 360)` and `roll` into
+    /// `[-180, 180)`.
+    pub fn normalized(&self) -> Self {
+        Self {
+            roll: self.roll.map(normalize_azimuth),
+            yaw: self.yaw.map(normalize_heading),
+            heading: self.heading.map(normalize_heading),
+            ..self.clone()
+        }
+    }
+
     pub fn update(&mut self, other: &Self) {
         if let Some(longitude) = other.longitude {
             self.longitude = Some(longitude);
@@ -878,80 +1494,80 @@ impl FromStr for Coords {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s, None, false)
+    }
+}
+
+impl Coords {
+    /// Parses coordinates, setting the components which fail to parse to
+    /// `None` instead of failing, and returning their errors. A wrong number
+    /// of components still fails.
+    pub fn from_str_lenient(s: &str) -> Result<(Self, Vec<Error>), Error> {
+        let mut errors = Vec::new();
+        let coords = Self::parse(s, Some(&mut errors), false)?;
+        Ok((coords, errors))
+    }
+
+    /// Reads the fourth field of the four-field layout as `heading` if
+    /// `four_field_heading`, as `roll` otherwise.
+    pub(crate) fn parse(
+        s: &str,
+        mut errors: Option<&mut Vec<Error>>,
+        four_field_heading: bool,
+    ) -> Result<Self, Error> {
         let mut tokens = s.split('|');
 
         let longitude = tokens
             .next()
             .ok_or_else(|| Error::MalformedCoords(s.to_string()))?;
-        let longitude = if longitude.is_empty() {
-            None
-        } else {
-            Some(longitude.parse().map_err(Error::ParseFloat)?)
-        };
+        let longitude = parse_coord(longitude, errors.as_deref_mut())?;
         let latitude = tokens
             .next()
             .ok_or_else(|| Error::MalformedCoords(s.to_string()))?;
-        let latitude = if latitude.is_empty() {
-            None
-        } else {
-            Some(latitude.parse().map_err(Error::ParseFloat)?)
-        };
+        let latitude = parse_coord(latitude, errors.as_deref_mut())?;
         let altitude = tokens
             .next()
             .ok_or_else(|| Error::MalformedCoords(s.to_string()))?;
-        let altitude = if altitude.is_empty() {
-            None
-        } else {
-            Some(altitude.parse().map_err(Error::ParseFloat)?)
-        };
+        let altitude = parse_coord(altitude, errors.as_deref_mut())?;
 
         let v4 = tokens.next();
         if let Some(v4) = v4 {
-            let v4 = if v4.is_empty() {
-                None
-            } else {
-                Some(v4.parse().map_err(Error::ParseFloat)?)
-            };
-            let v5 = tokens
-                .next()
-                .ok_or_else(|| Error::MalformedCoords(s.to_string()))?;
-            let v5 = if v5.is_empty() {
-                None
-            } else {
-                Some(v5.parse().map_err(Error::ParseFloat)?)
+            let v4 = parse_coord(v4, errors.as_deref_mut())?;
+            let Some(v5) = tokens.next() else {
+                // Not a layout from the specification, but some 2D exporters
+                // send `T=Lon|Lat|Alt|Heading`
+                let coords = Self {
+                    longitude,
+                    latitude,
+                    altitude,
+                    ..Default::default()
+                };
+                return Ok(if four_field_heading {
+                    Self {
+                        heading: v4,
+                        ..coords
+                    }
+                } else {
+                    Self { roll: v4, ..coords }
+                });
             };
+            let v5 = parse_coord(v5, errors.as_deref_mut())?;
 
             let v6 = tokens.next();
             if let Some(v6) = v6 {
-                let v6 = if v6.is_empty() {
-                    None
-                } else {
-                    Some(v6.parse().map_err(Error::ParseFloat)?)
-                };
+                let v6 = parse_coord(v6, errors.as_deref_mut())?;
 
                 let v7 = tokens.next();
                 if let Some(v7) = v7 {
-                    let v7 = if v7.is_empty() {
-                        None
-                    } else {
-                        Some(v7.parse().map_err(Error::ParseFloat)?)
-                    };
+                    let v7 = parse_coord(v7, errors.as_deref_mut())?;
                     let v8 = tokens
                         .next()
                         .ok_or_else(|| Error::MalformedCoords(s.to_string()))?;
-                    let v8 = if v8.is_empty() {
-                        None
-                    } else {
-                        Some(v8.parse().map_err(Error::ParseFloat)?)
-                    };
+                    let v8 = parse_coord(v8, errors.as_deref_mut())?;
                     let v9 = tokens
                         .next()
                         .ok_or_else(|| Error::MalformedCoords(s.to_string()))?;
-                    let v9 = if v9.is_empty() {
-                        None
-                    } else {
-                        Some(v9.parse().map_err(Error::ParseFloat)?)
-                    };
+                    let v9 = parse_coord(v9, errors)?;
 
                     Ok(Self {
                         longitude,
@@ -1006,6 +1622,83 @@ impl FromStr for Coords {
     }
 }
 
+/// Parses a component of [`Coords`], where an empty value means unchanged.
+fn parse_coord(value: &str, errors: Option<&mut Vec<Error>>) -> Result<Option<f64>, Error> {
+    if value.is_empty() {
+        return Ok(None);
+    }
+    match value.parse() {
+        Ok(value) => Ok(Some(value)),
+        Err(e) => match errors {
+            Some(errors) => {
+                errors.push(Error::ParseFloat(e));
+                Ok(None)
+            }
+            None => Err(Error::ParseFloat(e)),
+        },
+    }
+}
+
+/// Same as [`Coords::lat_lon_alt`], failing with
+/// [`Error::MalformedCoords`] when a component is missing.
+impl TryFrom<&Coords> for (f64, f64, f64) {
+    type Error = Error;
+
+    fn try_from(coords: &Coords) -> Result<Self, Self::Error> {
+        coords
+            .lat_lon_alt()
+            .ok_or_else(|| Error::MalformedCoords(coords.to_string()))
+    }
+}
+
+impl TryFrom<Coords> for (f64, f64, f64) {
+    type Error = Error;
+
+    fn try_from(coords: Coords) -> Result<Self, Self::Error> {
+        Self::try_from(&coords)
+    }
+}
+
+impl fmt::Display for Coords {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let has_orientation = self.roll.is_some() || self.pitch.is_some() || self.yaw.is_some();
+        let has_uv = self.u.is_some() || self.v.is_some();
+
+        let fields: &[Option<f64>] = if self.heading.is_some() || (has_orientation && has_uv) {
+            &[
+                self.longitude,
+                self.latitude,
+                self.altitude,
+                self.roll,
+                self.pitch,
+                self.yaw,
+                self.u,
+                self.v,
+                self.heading,
+            ]
+        } else if has_orientation {
+            &[
+                self.longitude,
+                self.latitude,
+                self.altitude,
+                self.roll,
+                self.pitch,
+                self.yaw,
+            ]
+        } else if has_uv {
+            &[self.longitude, self.latitude, self.altitude, self.u, self.v]
+        } else {
+            &[self.longitude, self.latitude, self.altitude]
+        };
+
+        let fields = fields
+            .iter()
+            .map(|field| field.map(|field| field.to_string()).unwrap_or_default())
+            .join("|");
+        write!(f, "{fields}")
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Tag {
     // Class
@@ -1122,6 +1815,174 @@ impl FromStr for Tag {
     }
 }
 
+impl Tag {
+    pub fn kind(&self) -> TagKind {
+        match self {
+            Self::Air
+            | Self::Ground
+            | Self::Sea
+            | Self::Weapon
+            | Self::Sensor
+            | Self::Navaid
+            | Self::Misc => TagKind::Class,
+            Self::Static | Self::Heavy | Self::Medium | Self::Light | Self::Minor => {
+                TagKind::Attribute
+            }
+            Self::FixedWing
+            | Self::Rotorcraft
+            | Self::Armor
+            | Self::AntiAircraft
+            | Self::Vehicle
+            | Self::Watercraft
+            | Self::Human
+            | Self::Biologic
+            | Self::Missile
+            | Self::Rocket
+            | Self::Bomb
+            | Self::Torpedo
+            | Self::Projectile
+            | Self::Beam
+            | Self::Decoy
+            | Self::Building
+            | Self::Bullseye
+            | Self::Waypoint => TagKind::BasicType,
+            Self::Tank
+            | Self::Warship
+            | Self::AircraftCarrier
+            | Self::Submarine
+            | Self::Infantry
+            | Self::Parachutist
+            | Self::Shell
+            | Self::Bullet
+            | Self::Grenade
+            | Self::Flare
+            | Self::Chaff
+            | Self::SmokeGrenade
+            | Self::Aerodrome
+            | Self::Container
+            | Self::Shrapnel
+            | Self::Explosion => TagKind::SpecificType,
+            Self::Other(_) => TagKind::Other,
+        }
+    }
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Air => write!(f, "Air"),
+            Self::Ground => write!(f, "Ground"),
+            Self::Sea => write!(f, "Sea"),
+            Self::Weapon => write!(f, "Weapon"),
+            Self::Sensor => write!(f, "Sensor"),
+            Self::Navaid => write!(f, "Navaid"),
+            Self::Misc => write!(f, "Misc"),
+            Self::Static => write!(f, "Static"),
+            Self::Heavy => write!(f, "Heavy"),
+            Self::Medium => write!(f, "Medium"),
+            Self::Light => write!(f, "Light"),
+            Self::Minor => write!(f, "Minor"),
+            Self::FixedWing => write!(f, "FixedWing"),
+            Self::Rotorcraft => write!(f, "Rotorcraft"),
+            Self::Armor => write!(f, "Armor"),
+            Self::AntiAircraft => write!(f, "AntiAircraft"),
+            Self::Vehicle => write!(f, "Vehicle"),
+            Self::Watercraft => write!(f, "Watercraft"),
+            Self::Human => write!(f, "Human"),
+            Self::Biologic => write!(f, "Biologic"),
+            Self::Missile => write!(f, "Missile"),
+            Self::Rocket => write!(f, "Rocket"),
+            Self::Bomb => write!(f, "Bomb"),
+            Self::Torpedo => write!(f, "Torpedo"),
+            Self::Projectile => write!(f, "Projectile"),
+            Self::Beam => write!(f, "Beam"),
+            Self::Decoy => write!(f, "Decoy"),
+            Self::Building => write!(f, "Building"),
+            Self::Bullseye => write!(f, "Bullseye"),
+            Self::Waypoint => write!(f, "Waypoint"),
+            Self::Tank => write!(f, "Tank"),
+            Self::Warship => write!(f, "Warship"),
+            Self::AircraftCarrier => write!(f, "AircraftCarrier"),
+            Self::Submarine => write!(f, "Submarine"),
+            Self::Infantry => write!(f, "Infantry"),
+            Self::Parachutist => write!(f, "Parachutist"),
+            Self::Shell => write!(f, "Shell"),
+            Self::Bullet => write!(f, "Bullet"),
+            Self::Grenade => write!(f, "Grenade"),
+            Self::Flare => write!(f, "Flare"),
+            Self::Chaff => write!(f, "Chaff"),
+            Self::SmokeGrenade => write!(f, "SmokeGrenade"),
+            Self::Aerodrome => write!(f, "Aerodrome"),
+            Self::Container => write!(f, "Container"),
+            Self::Shrapnel => write!(f, "Shrapnel"),
+            Self::Explosion => write!(f, "Explosion"),
+            Self::Other(tag) => write!(f, "{tag}"),
+        }
+    }
+}
+
+/// Layer of a [`Tag`]. A `Type` is usually made of one class, optional
+/// attributes, and basic and specific types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TagKind {
+    Class,
+    Attribute,
+    BasicType,
+    SpecificType,
+    Other,
+}
+
+macro_rules! mode_enum {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $name {
+            /// `0`
+            Off,
+            /// `1`
+            On,
+            /// Any other value, undocumented.
+            Unknown(u64),
+        }
+
+        impl $name {
+            /// Raw value of the property.
+            pub fn raw(self) -> u64 {
+                match self {
+                    Self::Off => 0,
+                    Self::On => 1,
+                    Self::Unknown(value) => value,
+                }
+            }
+        }
+
+        impl From<u64> for $name {
+            fn from(value: u64) -> Self {
+                match value {
+                    0 => Self::Off,
+                    1 => Self::On,
+                    value => Self::Unknown(value),
+                }
+            }
+        }
+    };
+}
+
+mode_enum!(
+    /// Typed [`ObjectProperty::RadarMode`].
+    RadarMode
+);
+mode_enum!(
+    /// Typed [`ObjectProperty::LockedTargetMode`], `Off` when there is no
+    /// lock or no target.
+    LockMode
+);
+mode_enum!(
+    /// Typed [`ObjectProperty::EngagementMode`] and
+    /// [`ObjectProperty::EngagementMode2`].
+    EngagementMode
+);
+
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Color {
     Red,
@@ -1136,6 +1997,27 @@ pub enum Color {
     Other(String),
 }
 
+impl Color {
+    /// Every color known by this crate, in the palette order of Tacview.
+    pub fn all_known() -> &'static [Color] {
+        &[
+            Self::Red,
+            Self::Orange,
+            Self::Yellow,
+            Self::Green,
+            Self::Cyan,
+            Self::Blue,
+            Self::Violet,
+        ]
+    }
+
+    /// `false` for colors added by newer Tacview versions, kept in
+    /// [`Other`](Self::Other).
+    pub fn is_known(&self) -> bool {
+        !matches!(self, Self::Other(_))
+    }
+}
+
 impl FromStr for Color {
     type Err = Error;
 
@@ -1152,3 +2034,345 @@ impl FromStr for Color {
         }
     }
 }
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Red => write!(f, "Red"),
+            Self::Orange => write!(f, "Orange"),
+            Self::Yellow => write!(f, "Yellow"),
+            Self::Green => write!(f, "Green"),
+            Self::Cyan => write!(f, "Cyan"),
+            Self::Blue => write!(f, "Blue"),
+            Self::Violet => write!(f, "Violet"),
+            Self::Other(color) => write!(f, "{color}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_non_float_round_trip() {
+        let texts = ["Jester", "F-16C-52", "a b", "x=y", ""];
+        let ids = [0x1, 0xA, 0x2D50A7, 0xFFFF_FFFF_FFFF];
+        let mut properties = vec![
+            ObjectProperty::T(Coords::position(1.5, 2.0, 3.0)),
+            ObjectProperty::Type([Tag::Air, Tag::FixedWing].into_iter().collect()),
+            ObjectProperty::Type([Tag::Other("Custom".to_string())].into_iter().collect()),
+            ObjectProperty::Color(Color::Blue),
+            ObjectProperty::Color(Color::Other("Pink".to_string())),
+        ];
+        for text in texts {
+            properties.extend(
+                [
+                    ObjectProperty::Callsign,
+                    ObjectProperty::Registration,
+                    ObjectProperty::Squawk,
+                    ObjectProperty::Icao24,
+                    ObjectProperty::Shape,
+                    ObjectProperty::Debug,
+                    ObjectProperty::Label,
+                ]
+                .map(|variant| variant(text.to_string())),
+            );
+            properties.extend(
+                [
+                    ObjectProperty::Name,
+                    ObjectProperty::Pilot,
+                    ObjectProperty::Group,
+                    ObjectProperty::Country,
+                    ObjectProperty::Coalition,
+                ]
+                .map(|variant| variant(text.into())),
+            );
+            properties.push(ObjectProperty::Unknown("FooBar".to_string(), text.into()));
+        }
+        for id in ids {
+            properties.extend(
+                [
+                    ObjectProperty::Parent,
+                    ObjectProperty::Next,
+                    ObjectProperty::FocusedTarget,
+                    ObjectProperty::LockedTarget,
+                    ObjectProperty::LockedTarget2,
+                    ObjectProperty::LockedTarget3,
+                    ObjectProperty::LockedTarget4,
+                    ObjectProperty::LockedTarget5,
+                    ObjectProperty::LockedTarget6,
+                    ObjectProperty::LockedTarget7,
+                    ObjectProperty::LockedTarget8,
+                    ObjectProperty::LockedTarget9,
+                    ObjectProperty::Importance,
+                    ObjectProperty::Slot,
+                    ObjectProperty::RadarMode,
+                    ObjectProperty::LockedTargetMode,
+                    ObjectProperty::EngagementMode,
+                    ObjectProperty::EngagementMode2,
+                    ObjectProperty::HeartRate,
+                ]
+                .map(|variant| variant(id)),
+            );
+        }
+        for value in [true, false] {
+            properties.push(ObjectProperty::Disabled(value));
+            properties.push(ObjectProperty::TriggerPressed(value));
+        }
+
+        for property in properties {
+            let token = property.to_string();
+            assert!(
+                token.starts_with(&format!("{}=", property.name())),
+                "{token}"
+            );
+            assert_eq!(
+                ObjectProperty::from_str(&token).unwrap(),
+                property,
+                "{token}"
+            );
+        }
+        assert_eq!(
+            ObjectProperty::Parent(0x2D50A7).to_string(),
+            "Parent=2D50A7"
+        );
+        assert_eq!(
+            ObjectProperty::Callsign("Jester".to_string()).to_string(),
+            "CallSign=Jester"
+        );
+        assert_eq!(
+            ObjectProperty::from_str("Callsign=Jester").unwrap(),
+            ObjectProperty::Callsign("Jester".to_string())
+        );
+        assert_eq!(
+            ObjectProperty::from_str("LockedTargetAzimuth=14.5").unwrap(),
+            ObjectProperty::LockedTargetAzimuth(14.5)
+        );
+    }
+
+    #[test]
+    fn test_bool_coercion() {
+        for (s, expected) in [
+            ("Disabled=1", true),
+            ("Disabled=0", false),
+            ("Disabled=true", true),
+            ("Disabled=False", false),
+        ] {
+            assert_eq!(
+                ObjectProperty::from_str(s).unwrap(),
+                ObjectProperty::Disabled(expected),
+                "{s}"
+            );
+        }
+        assert_eq!(
+            ObjectProperty::from_str("TriggerPressed=1.0").unwrap(),
+            ObjectProperty::TriggerPressed(true)
+        );
+    }
+
+    #[test]
+    fn test_surface_and_position() {
+        let surface = Coords::surface(-129.5, 43.25);
+        assert_eq!(surface.to_string(), "-129.5|43.25|");
+        assert_eq!(Coords::from_str(&surface.to_string()).unwrap(), surface);
+
+        let position = Coords::position(-129.5, 43.25, 300.0);
+        assert_eq!(position.to_string(), "-129.5|43.25|300");
+        assert_eq!(Coords::from_str(&position.to_string()).unwrap(), position);
+        assert_eq!(position.lat_lon_alt(), Some((43.25, -129.5, 300.0)));
+    }
+
+    #[test]
+    fn test_lat_lon_alt() {
+        let coords = Coords::from_str("-129.5|43.25|300").unwrap();
+        assert_eq!(coords.lat_lon_alt(), Some((43.25, -129.5, 300.0)));
+        let (lat, lon, alt) = <(f64, f64, f64)>::try_from(&coords).unwrap();
+        assert_eq!((lat, lon, alt), (43.25, -129.5, 300.0));
+        assert!(<(f64, f64, f64)>::try_from(coords).is_ok());
+
+        let partial = Coords::from_str("-129.5|43.25|").unwrap();
+        assert_eq!(partial.lat_lon_alt(), None);
+        assert!(matches!(
+            <(f64, f64, f64)>::try_from(&partial),
+            Err(Error::MalformedCoords(s)) if s == "-129.5|43.25|"
+        ));
+    }
+
+    #[test]
+    fn test_coords_layouts() {
+        let position = Coords::position(1.5, 2.5, 300.0);
+        let layouts = [
+            (position.clone(), "1.5|2.5|300"),
+            (
+                Coords {
+                    u: Some(10.0),
+                    v: Some(20.0),
+                    ..position.clone()
+                },
+                "1.5|2.5|300|10|20",
+            ),
+            (
+                Coords {
+                    roll: Some(-1.5),
+                    yaw: Some(185.0),
+                    ..position.clone()
+                },
+                "1.5|2.5|300|-1.5||185",
+            ),
+            (
+                Coords {
+                    roll: Some(-1.5),
+                    pitch: Some(2.0),
+                    yaw: Some(185.0),
+                    u: Some(10.0),
+                    v: Some(20.0),
+                    heading: Some(190.0),
+                    ..position.clone()
+                },
+                "1.5|2.5|300|-1.5|2|185|10|20|190",
+            ),
+            (
+                Coords {
+                    heading: Some(190.0),
+                    ..position
+                },
+                "1.5|2.5|300||||||190",
+            ),
+            (
+                Coords {
+                    altitude: Some(300.0),
+                    ..Default::default()
+                },
+                "||300",
+            ),
+        ];
+        for (coords, token) in layouts {
+            assert_eq!(coords.to_string(), token);
+            assert_eq!(Coords::from_str(token).unwrap(), coords, "{token}");
+        }
+    }
+
+    #[test]
+    fn test_coords_four_fields() {
+        let position = Coords::position(1.5, 2.5, 300.0);
+        assert_eq!(
+            Coords::from_str("1.5|2.5|300|185").unwrap(),
+            Coords {
+                roll: Some(185.0),
+                ..position.clone()
+            }
+        );
+        assert_eq!(
+            Coords::parse("1.5|2.5|300|185", None, true).unwrap(),
+            Coords {
+                heading: Some(185.0),
+                ..position
+            }
+        );
+
+        let coords = Coords::from_str("1.5|2.5|300|10|20").unwrap();
+        assert_eq!(coords.u, Some(10.0));
+        assert_eq!(coords.v, Some(20.0));
+        assert_eq!(coords.heading, None);
+    }
+
+    #[test]
+    fn test_tag_kind() {
+        let expected = [
+            (TagKind::Class, "Air+Ground+Sea+Weapon+Sensor+Navaid+Misc"),
+            (TagKind::Attribute, "Static+Heavy+Medium+Light+Minor"),
+            (
+                TagKind::BasicType,
+                "FixedWing+Rotorcraft+Armor+AntiAircraft+Vehicle+Watercraft+Human+Biologic+Missile+Rocket+Bomb+Torpedo+Projectile+Beam+Decoy+Building+Bullseye+Waypoint",
+            ),
+            (
+                TagKind::SpecificType,
+                "Tank+Warship+AircraftCarrier+Submarine+Infantry+Parachutist+Shell+Bullet+Grenade+Flare+Chaff+SmokeGrenade+Aerodrome+Container+Shrapnel+Explosion",
+            ),
+            (TagKind::Other, "Spaceship"),
+        ];
+        for (kind, tags) in expected {
+            for tag in tags.split('+') {
+                assert_eq!(Tag::from_str(tag).unwrap().kind(), kind, "{tag}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_known_colors() {
+        for color in Color::all_known() {
+            assert!(color.is_known());
+            assert_eq!(&Color::from_str(&color.to_string()).unwrap(), color);
+        }
+        assert_eq!(Color::all_known().len(), 7);
+
+        let color = Color::from_str("Magenta").unwrap();
+        assert!(!color.is_known());
+        assert!(!Color::all_known().contains(&color));
+    }
+
+    #[test]
+    fn test_bearing_range_from_bullseye() {
+        let mut bullseye = ObjectState::default();
+        bullseye.update(ObjectProperty::from_str("T=0|0|0").unwrap());
+
+        let north = Coords::from_str("0|1|5000").unwrap();
+        let (bearing, range) = north.bearing_range_from_bullseye(&bullseye, None).unwrap();
+        assert!(bearing.abs() < 1e-9);
+        assert!((range - 111_195.0).abs() < 1.0);
+
+        let west = Coords::from_str("-1|0|5000").unwrap();
+        let (bearing, _) = west.bearing_range_from_bullseye(&bullseye, None).unwrap();
+        assert!((bearing - 270.0).abs() < 1e-9);
+
+        let unknown = Coords::from_str("||5000").unwrap();
+        assert_eq!(unknown.bearing_range_from_bullseye(&bullseye, None), None);
+    }
+
+    #[test]
+    fn test_bearing_range_with_reference() {
+        let mut bullseye = ObjectState::default();
+        bullseye.update(ObjectProperty::from_str("T=0|0|0").unwrap());
+
+        // one degree of longitude east of the bullseye, at 42°N 71°W
+        let east = Coords::from_str("1|0|5000").unwrap();
+        let (bearing, range) = east
+            .bearing_range_from_bullseye(&bullseye, Some((-71.0, 42.0)))
+            .unwrap();
+        assert!((bearing - 89.665).abs() < 1e-3);
+        assert!((range - 82_633.6).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_normalized() {
+        assert_eq!(
+            ObjectProperty::from_str("HDG=370").unwrap().normalized(),
+            ObjectProperty::Hdg(10.0)
+        );
+        assert_eq!(
+            ObjectProperty::from_str("RadarAzimuth=-20")
+                .unwrap()
+                .normalized(),
+            ObjectProperty::RadarAzimuth(-20.0)
+        );
+        assert_eq!(
+            ObjectProperty::from_str("RadarAzimuth=340")
+                .unwrap()
+                .normalized(),
+            ObjectProperty::RadarAzimuth(-20.0)
+        );
+        assert_eq!(
+            ObjectProperty::from_str("HDG=-20").unwrap(),
+            ObjectProperty::Hdg(-20.0)
+        );
+
+        let coords = Coords::from_str("1|2|3|190|5|-90|7|8|370")
+            .unwrap()
+            .normalized();
+        assert_eq!(coords.roll, Some(-170.0));
+        assert_eq!(coords.pitch, Some(5.0));
+        assert_eq!(coords.yaw, Some(270.0));
+        assert_eq!(coords.heading, Some(10.0));
+    }
+}
@@ -0,0 +1,133 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures_core::Stream;
+use tokio::time::{sleep, Sleep};
+
+use crate::error::Result;
+
+use super::record::Record;
+
+/// Replays recorded ACMI records at (scaled) real time speed, sleeping before
+/// each `Frame` record according to the delta from the previous frame.
+///
+/// `speed` is a multiplier: `2.0` replays twice as fast as recorded, and
+/// `f64::INFINITY` replays without any delay. Records are pulled from the
+/// wrapped iterator inside `poll_next`, so it should be cheap to advance (like
+/// a [`FileReader`](super::file::FileReader)). Delays too long to be
+/// represented are clamped to [`Duration::MAX`].
+#[derive(Debug)]
+pub struct ReplayReader<I> {
+    records: I,
+    speed: f64,
+    last_frame: Option<f64>,
+    delayed: Option<(Pin<Box<Sleep>>, Record)>,
+}
+
+impl<I> ReplayReader<I>
+where
+    I: Iterator<Item = Result<Record>>,
+{
+    /// # Panics
+    ///
+    /// Panics if `speed` is not strictly positive, NaN included.
+    pub fn new(records: I, speed: f64) -> Self {
+        assert!(speed > 0.0, "replay speed must be positive, got {speed}");
+        Self {
+            records,
+            speed,
+            last_frame: None,
+            delayed: None,
+        }
+    }
+}
+
+impl<I> Stream for ReplayReader<I>
+where
+    I: Iterator<Item = Result<Record>> + Unpin,
+{
+    type Item = Result<Record>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if let Some((delay, _)) = &mut this.delayed {
+            if delay.as_mut().poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+            let (_, record) = this.delayed.take().unwrap();
+            return Poll::Ready(Some(Ok(record)));
+        }
+
+        let record = match this.records.next() {
+            Some(Ok(record)) => record,
+            other => return Poll::Ready(other),
+        };
+
+        if let Record::Frame(time) = record {
+            let last_frame = this.last_frame.replace(time);
+            let delay = last_frame.map_or(0.0, |last_frame| (time - last_frame) / this.speed);
+            if delay.is_finite() && delay > 0.0 {
+                let delay = Duration::try_from_secs_f64(delay).unwrap_or(Duration::MAX);
+                let mut delay = Box::pin(sleep(delay));
+                if delay.as_mut().poll(cx).is_pending() {
+                    this.delayed = Some((delay, record));
+                    return Poll::Pending;
+                }
+            }
+        }
+
+        Poll::Ready(Some(Ok(record)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures_util::StreamExt;
+    use tokio::time::Instant;
+
+    use super::*;
+
+    fn mission() -> impl Iterator<Item = Result<Record>> + Unpin {
+        [
+            Record::Frame(0.0),
+            Record::Remove(1),
+            Record::Frame(1.0),
+            Record::Frame(3.0),
+        ]
+        .into_iter()
+        .map(Ok)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_replay_timing() {
+        let start = Instant::now();
+        let records: Vec<_> = ReplayReader::new(mission(), 10.0).collect().await;
+        assert_eq!(records.len(), 4);
+        assert_eq!(start.elapsed(), Duration::from_millis(300));
+
+        let start = Instant::now();
+        let records: Vec<_> = ReplayReader::new(mission(), f64::INFINITY).collect().await;
+        assert_eq!(records.len(), 4);
+        assert_eq!(start.elapsed(), Duration::ZERO);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_replay_huge_delay() {
+        let mut replay = ReplayReader::new(mission(), f64::MIN_POSITIVE);
+        assert_eq!(replay.next().await.unwrap().unwrap(), Record::Frame(0.0));
+        assert_eq!(replay.next().await.unwrap().unwrap(), Record::Remove(1));
+        let next = tokio::time::timeout(Duration::from_secs(3600), replay.next()).await;
+        assert!(next.is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "replay speed must be positive")]
+    fn test_replay_zero_speed() {
+        ReplayReader::new(mission(), 0.0);
+    }
+}
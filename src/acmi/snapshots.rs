@@ -0,0 +1,107 @@
+use tokio::io::AsyncBufRead;
+
+use crate::error::Result;
+
+use super::{record::Record, state::ObjectState, world::World, RealTimeReader};
+
+/// Stream of `(frame_time, id, state)` rows, yielding the merged state of an
+/// object every time it is updated. Created with
+/// [`RealTimeReader::object_snapshots`].
+///
+/// Removed objects yield nothing by default. With
+/// [`with_removals`](Self::with_removals), the last known state of a removed
+/// object is yielded one final time, stamped with the time of the removal.
+#[derive(Debug)]
+pub struct ObjectSnapshots<R> {
+    reader: RealTimeReader<R>,
+    world: World,
+    with_removals: bool,
+}
+
+impl<R> ObjectSnapshots<R>
+where
+    R: AsyncBufRead + Unpin,
+{
+    pub(super) fn new(reader: RealTimeReader<R>) -> Self {
+        Self {
+            reader,
+            world: World::new(),
+            with_removals: false,
+        }
+    }
+
+    pub fn with_removals(mut self, with_removals: bool) -> Self {
+        self.with_removals = with_removals;
+        self
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub async fn next(&mut self) -> Result<Option<(f64, u64, ObjectState)>> {
+        while let Some(record) = self.reader.next().await? {
+            match record {
+                Record::Update(id, _) => {
+                    self.world.apply(record);
+                    if let Some(object) = self.world.object(id) {
+                        return Ok(Some((self.world.time(), id, object.clone())));
+                    }
+                }
+                Record::Remove(id) => {
+                    let object = self.world.object(id).cloned();
+                    self.world.apply(record);
+                    if let Some(object) = object.filter(|_| self.with_removals) {
+                        return Ok(Some((self.world.time(), id, object)));
+                    }
+                }
+                record => {
+                    self.world.apply(record);
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ACMI: &[u8] = b"FileType=text/acmi/tacview
+FileVersion=2.2
+#0
+A,T=1|2|3,Name=F-16C-52
+B,T=4|5|6
+#1.5
+A,T=||300
+-B
+";
+
+    #[tokio::test]
+    async fn test_object_snapshots() {
+        let reader = RealTimeReader::try_from_reader(ACMI).await.unwrap();
+        let mut snapshots = reader.object_snapshots();
+        let mut rows = Vec::new();
+        while let Some((time, id, object)) = snapshots.next().await.unwrap() {
+            rows.push((time, id, object.coords().unwrap().altitude));
+        }
+        assert_eq!(
+            rows,
+            vec![
+                (0.0, 0xA, Some(3.0)),
+                (0.0, 0xB, Some(6.0)),
+                (1.5, 0xA, Some(300.0)),
+            ]
+        );
+
+        let reader = RealTimeReader::try_from_reader(ACMI).await.unwrap();
+        let mut snapshots = reader.object_snapshots().with_removals(true);
+        let mut rows = Vec::new();
+        while let Some((time, id, _)) = snapshots.next().await.unwrap() {
+            rows.push((time, id));
+        }
+        assert_eq!(rows, vec![(0.0, 0xA), (0.0, 0xB), (1.5, 0xA), (1.5, 0xB)]);
+        assert!(snapshots.world().object(0xB).is_none());
+    }
+}
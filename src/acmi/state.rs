@@ -0,0 +1,742 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    mem::discriminant,
+};
+
+use super::record::{
+    object_property::{Coords, EngagementMode, LockMode, ObjectProperty, RadarMode, Tag},
+    PropertyKey, UnknownValue,
+};
+
+const KNOTS_PER_METER_PER_SECOND: f64 = 3600.0 / 1852.0;
+
+macro_rules! find_property {
+    ($state:expr, $variant:ident) => {
+        $state
+            .properties
+            .get(&PropertyKey::Known(discriminant(
+                &ObjectProperty::$variant(Default::default()),
+            )))
+            .and_then(|property| match property {
+                ObjectProperty::$variant(value) => Some(value),
+                _ => None,
+            })
+    };
+}
+
+/// Accumulated state of a single object, built from its `Update` records.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ObjectState {
+    properties: HashMap<PropertyKey<ObjectProperty>, ObjectProperty>,
+    updated_at: HashMap<PropertyKey<ObjectProperty>, f64>,
+    metadata: Option<Metadata>,
+}
+
+impl<'a> IntoIterator for &'a ObjectState {
+    type Item = &'a ObjectProperty;
+    type IntoIter =
+        std::collections::hash_map::Values<'a, PropertyKey<ObjectProperty>, ObjectProperty>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.properties.values()
+    }
+}
+
+/// Display metadata of an object from an external database, see
+/// [`World::set_name_resolver`](super::world::World::set_name_resolver).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metadata {
+    pub full_name: Option<String>,
+    pub role: Option<String>,
+    pub image: Option<String>,
+}
+
+impl ObjectState {
+    /// Applies a property, replacing the previous value of the same
+    /// [key](ObjectProperty::key). Coordinates are merged with
+    /// [`Coords::update`] since Tacview only sends the changed components.
+    pub fn update(&mut self, property: ObjectProperty) {
+        let key = property.key();
+        if let ObjectProperty::T(coords) = &property {
+            if let Some(ObjectProperty::T(current)) = self.properties.get_mut(&key) {
+                current.update(coords);
+                return;
+            }
+        }
+        self.properties.insert(key, property);
+    }
+
+    /// Same as [`update`](Self::update), recording the frame time of the
+    /// update for [`age_of`](Self::age_of).
+    pub fn update_at(&mut self, property: ObjectProperty, time: f64) {
+        self.updated_at.insert(property.key(), time);
+        self.update(property);
+    }
+
+    /// Seconds elapsed since the property, by [name](ObjectProperty::name),
+    /// was last updated with [`update_at`](Self::update_at).
+    pub fn age_of(&self, name: &str, now: f64) -> Option<f64> {
+        let key = self
+            .properties
+            .iter()
+            .find_map(|(key, property)| (property.name() == name).then_some(key))?;
+        let updated_at = self.updated_at.get(key)?;
+        Some(now - updated_at)
+    }
+
+    /// Approximate heap size of the state, ignoring the content of strings and
+    /// tag sets.
+    pub(crate) fn estimated_bytes(&self) -> usize {
+        self.properties.capacity()
+            * std::mem::size_of::<(PropertyKey<ObjectProperty>, ObjectProperty)>()
+            + self.updated_at.capacity() * std::mem::size_of::<(PropertyKey<ObjectProperty>, f64)>()
+    }
+
+    /// Every known property, in no particular order, the coordinates merged
+    /// into a single `T`. Pairs with [`ObjectProperty::name`], e.g. to render
+    /// a property table.
+    pub fn properties(&self) -> impl Iterator<Item = &ObjectProperty> {
+        self.properties.values()
+    }
+
+    /// Property of the same [key](ObjectProperty::key), if known.
+    pub fn property(&self, key: &PropertyKey<ObjectProperty>) -> Option<&ObjectProperty> {
+        self.properties.get(key)
+    }
+
+    /// Value of the property unknown to this crate with the given name.
+    pub fn unknown(&self, name: &str) -> Option<&UnknownValue> {
+        match self
+            .properties
+            .get(&PropertyKey::Unknown(name.to_string()))?
+        {
+            ObjectProperty::Unknown(_, value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn coords(&self) -> Option<&Coords> {
+        find_property!(self, T)
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        find_property!(self, Name).map(AsRef::as_ref)
+    }
+
+    pub fn tags(&self) -> Option<&HashSet<Tag>> {
+        find_property!(self, Type)
+    }
+
+    /// Metadata resolved from the name and tags of the object, if any.
+    pub fn metadata(&self) -> Option<&Metadata> {
+        self.metadata.as_ref()
+    }
+
+    pub(crate) fn set_metadata(&mut self, metadata: Option<Metadata>) {
+        self.metadata = metadata;
+    }
+
+    pub fn coalition(&self) -> Option<&str> {
+        find_property!(self, Coalition).map(AsRef::as_ref)
+    }
+
+    pub fn callsign(&self) -> Option<&str> {
+        find_property!(self, Callsign).map(String::as_str)
+    }
+
+    pub fn group(&self) -> Option<&str> {
+        find_property!(self, Group).map(AsRef::as_ref)
+    }
+
+    /// Position of the object in its group, `0` for the leader.
+    pub fn slot(&self) -> Option<u64> {
+        find_property!(self, Slot).copied()
+    }
+
+    /// Radius of the object, e.g. animated for expanding explosions.  
+    /// Unit: m
+    pub fn radius_m(&self) -> Option<f64> {
+        find_property!(self, Radius).copied()
+    }
+
+    /// Visibility ratio, from 0 (invisible) to 1 (fully visible).
+    pub fn visibility(&self) -> Option<f64> {
+        find_property!(self, Visible).copied()
+    }
+
+    pub fn health(&self) -> Option<f64> {
+        find_property!(self, Health).copied()
+    }
+
+    /// Whether the `Health` of the object is depleted. Tacview does not
+    /// remove such objects by itself, and may not send a `Destroyed` event.
+    pub fn is_destroyed(&self) -> bool {
+        self.health().is_some_and(|health| health <= 0.0)
+    }
+
+    /// Gravitational force equivalent of the acceleration in each axis, if any
+    /// of them is known.
+    pub fn gforce(&self) -> Option<GForce> {
+        let gforce = GForce {
+            vertical: find_property!(self, VerticalGForce).copied(),
+            longitudinal: find_property!(self, LongitudinalGForce).copied(),
+            lateral: find_property!(self, LateralGForce).copied(),
+        };
+        (gforce != GForce::default()).then_some(gforce)
+    }
+
+    /// Raw player HOTAS/Yoke position, if any axis is known.
+    pub fn control_inputs(&self) -> Option<Axes> {
+        let axes = Axes {
+            roll: find_property!(self, RollControlInput).copied(),
+            pitch: find_property!(self, PitchControlInput).copied(),
+            yaw: find_property!(self, YawControlInput).copied(),
+        };
+        (axes != Axes::default()).then_some(axes)
+    }
+
+    /// HOTAS/Yoke position in the cockpit, if any axis is known.
+    pub fn control_positions(&self) -> Option<Axes> {
+        let axes = Axes {
+            roll: find_property!(self, RollControlPosition).copied(),
+            pitch: find_property!(self, PitchControlPosition).copied(),
+            yaw: find_property!(self, YawControlPosition).copied(),
+        };
+        (axes != Axes::default()).then_some(axes)
+    }
+
+    pub fn radar(&self) -> RadarState {
+        RadarState {
+            mode: find_property!(self, RadarMode).copied(),
+            azimuth: find_property!(self, RadarAzimuth).copied(),
+            elevation: find_property!(self, RadarElevation).copied(),
+            roll: find_property!(self, RadarRoll).copied(),
+            range: find_property!(self, RadarRange).copied(),
+            horizontal_beamwidth: find_property!(self, RadarHorizontalBeamwidth).copied(),
+            vertical_beamwidth: find_property!(self, RadarVerticalBeamwidth).copied(),
+            range_gate_azimuth: find_property!(self, RadarRangeGateAzimuth).copied(),
+            range_gate_elevation: find_property!(self, RadarRangeGateElevation).copied(),
+            range_gate_roll: find_property!(self, RadarRangeGateRoll).copied(),
+            range_gate_min: find_property!(self, RadarRangeGateMin).copied(),
+            range_gate_max: find_property!(self, RadarRangeGateMax).copied(),
+            range_gate_horizontal_beamwidth: find_property!(
+                self,
+                RadarRangeGateHorizontalBeamwidth
+            )
+            .copied(),
+            range_gate_vertical_beamwidth: find_property!(self, RadarRangeGateVerticalBeamwidth)
+                .copied(),
+        }
+    }
+
+    pub fn radar_mode(&self) -> Option<RadarMode> {
+        find_property!(self, RadarMode)
+            .copied()
+            .map(RadarMode::from)
+    }
+
+    pub fn locked_target_mode(&self) -> Option<LockMode> {
+        find_property!(self, LockedTargetMode)
+            .copied()
+            .map(LockMode::from)
+    }
+
+    pub fn engagement_mode(&self) -> Option<EngagementMode> {
+        find_property!(self, EngagementMode)
+            .copied()
+            .map(EngagementMode::from)
+    }
+
+    pub fn engagement_mode2(&self) -> Option<EngagementMode> {
+        find_property!(self, EngagementMode2)
+            .copied()
+            .map(EngagementMode::from)
+    }
+
+    /// Engagement envelopes of anti-aircraft units, one for each indexed
+    /// `EngagementRange` which is known. An envelope without vertical range is
+    /// a sphere, and is active unless its `EngagementMode` is 0.
+    pub fn engagement_envelopes(&self) -> Vec<Envelope> {
+        [
+            (
+                find_property!(self, EngagementMode),
+                find_property!(self, EngagementRange),
+                find_property!(self, VerticalEngagementRange),
+            ),
+            (
+                find_property!(self, EngagementMode2),
+                find_property!(self, EngagementRange2),
+                find_property!(self, VerticalEngagementRange2),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(mode, range, vertical_range)| {
+            let range = *range?;
+            Some(Envelope {
+                active: mode.is_none_or(|mode| *mode != 0),
+                range,
+                vertical_range: vertical_range.copied().unwrap_or(range),
+            })
+        })
+        .collect()
+    }
+
+    /// Pilot physiology, if any of it is known.
+    pub fn physiology(&self) -> Option<Physiology> {
+        let physiology = Physiology {
+            heart_rate: find_property!(self, HeartRate).copied(),
+            spo2: find_property!(self, SpO2).copied(),
+            enl: find_property!(self, Enl).copied(),
+        };
+        (physiology != Physiology::default()).then_some(physiology)
+    }
+
+    /// Flight control surfaces and trim tabs.
+    pub fn controls(&self) -> Controls {
+        Controls {
+            aileron_left: find_property!(self, AileronLeft).copied(),
+            aileron_right: find_property!(self, AileronRight).copied(),
+            elevator: find_property!(self, Elevator).copied(),
+            rudder: find_property!(self, Rudder).copied(),
+            roll_trim: find_property!(self, RollTrimTab).copied(),
+            pitch_trim: find_property!(self, PitchTrimTab).copied(),
+            yaw_trim: find_property!(self, YawTrimTab).copied(),
+        }
+    }
+
+    /// Pilot head orientation relative to the aircraft.
+    pub fn pilot_head(&self) -> PilotHead {
+        PilotHead {
+            roll: find_property!(self, PilotHeadRoll).copied(),
+            pitch: find_property!(self, PilotHeadPitch).copied(),
+            yaw: find_property!(self, PilotHeadYaw).copied(),
+        }
+    }
+
+    pub fn fuel(&self) -> FuelState {
+        let mut fuel = FuelState::default();
+        for property in self.properties.values() {
+            let (slot, value) = match property {
+                ObjectProperty::FuelWeight(v) => (&mut fuel.tank_weights[0], v),
+                ObjectProperty::FuelWeight2(v) => (&mut fuel.tank_weights[1], v),
+                ObjectProperty::FuelWeight3(v) => (&mut fuel.tank_weights[2], v),
+                ObjectProperty::FuelWeight4(v) => (&mut fuel.tank_weights[3], v),
+                ObjectProperty::FuelWeight5(v) => (&mut fuel.tank_weights[4], v),
+                ObjectProperty::FuelWeight6(v) => (&mut fuel.tank_weights[5], v),
+                ObjectProperty::FuelWeight7(v) => (&mut fuel.tank_weights[6], v),
+                ObjectProperty::FuelWeight8(v) => (&mut fuel.tank_weights[7], v),
+                ObjectProperty::FuelWeight9(v) => (&mut fuel.tank_weights[8], v),
+                ObjectProperty::FuelVolume(v) => (&mut fuel.tank_volumes[0], v),
+                ObjectProperty::FuelVolume2(v) => (&mut fuel.tank_volumes[1], v),
+                ObjectProperty::FuelVolume3(v) => (&mut fuel.tank_volumes[2], v),
+                ObjectProperty::FuelVolume4(v) => (&mut fuel.tank_volumes[3], v),
+                ObjectProperty::FuelVolume5(v) => (&mut fuel.tank_volumes[4], v),
+                ObjectProperty::FuelVolume6(v) => (&mut fuel.tank_volumes[5], v),
+                ObjectProperty::FuelVolume7(v) => (&mut fuel.tank_volumes[6], v),
+                ObjectProperty::FuelVolume8(v) => (&mut fuel.tank_volumes[7], v),
+                ObjectProperty::FuelVolume9(v) => (&mut fuel.tank_volumes[8], v),
+                ObjectProperty::FuelFlowWeight(v) => (&mut fuel.flow_weights[0], v),
+                ObjectProperty::FuelFlowWeight2(v) => (&mut fuel.flow_weights[1], v),
+                ObjectProperty::FuelFlowWeight3(v) => (&mut fuel.flow_weights[2], v),
+                ObjectProperty::FuelFlowWeight4(v) => (&mut fuel.flow_weights[3], v),
+                ObjectProperty::FuelFlowWeight5(v) => (&mut fuel.flow_weights[4], v),
+                ObjectProperty::FuelFlowWeight6(v) => (&mut fuel.flow_weights[5], v),
+                ObjectProperty::FuelFlowWeight7(v) => (&mut fuel.flow_weights[6], v),
+                ObjectProperty::FuelFlowVolume(v) => (&mut fuel.flow_volumes[0], v),
+                ObjectProperty::FuelFlowVolume2(v) => (&mut fuel.flow_volumes[1], v),
+                ObjectProperty::FuelFlowVolume3(v) => (&mut fuel.flow_volumes[2], v),
+                ObjectProperty::FuelFlowVolume4(v) => (&mut fuel.flow_volumes[3], v),
+                ObjectProperty::FuelFlowVolume5(v) => (&mut fuel.flow_volumes[4], v),
+                ObjectProperty::FuelFlowVolume6(v) => (&mut fuel.flow_volumes[5], v),
+                ObjectProperty::FuelFlowVolume7(v) => (&mut fuel.flow_volumes[6], v),
+                _ => continue,
+            };
+            *slot = Some(*value);
+        }
+        fuel
+    }
+
+    /// Human-readable one-line summary, e.g.
+    /// `F-16C-52 "Jester" HDG=185 ALT=1500m IAS=135kt`
+    pub fn summary(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// Unit: g
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct GForce {
+    pub vertical: Option<f64>,
+    pub longitudinal: Option<f64>,
+    pub lateral: Option<f64>,
+}
+
+/// Unit: ratio
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Axes {
+    pub roll: Option<f64>,
+    pub pitch: Option<f64>,
+    pub yaw: Option<f64>,
+}
+
+/// Radar state, relative to the aircraft orientation. See the `Radar*`
+/// variants of [`ObjectProperty`] for the meaning and unit of each field.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RadarState {
+    pub mode: Option<u64>,
+    pub azimuth: Option<f64>,
+    pub elevation: Option<f64>,
+    pub roll: Option<f64>,
+    pub range: Option<f64>,
+    pub horizontal_beamwidth: Option<f64>,
+    pub vertical_beamwidth: Option<f64>,
+    pub range_gate_azimuth: Option<f64>,
+    pub range_gate_elevation: Option<f64>,
+    pub range_gate_roll: Option<f64>,
+    pub range_gate_min: Option<f64>,
+    pub range_gate_max: Option<f64>,
+    pub range_gate_horizontal_beamwidth: Option<f64>,
+    pub range_gate_vertical_beamwidth: Option<f64>,
+}
+
+/// Engagement bubble of an anti-aircraft unit.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Envelope {
+    pub active: bool,
+    /// Horizontal radius.  
+    /// Unit: m
+    pub range: f64,
+    /// Vertical radius, equal to `range` for a sphere.  
+    /// Unit: m
+    pub vertical_range: f64,
+}
+
+/// Control surfaces position and trim tabs.  
+/// Unit: ratio
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Controls {
+    pub aileron_left: Option<f64>,
+    pub aileron_right: Option<f64>,
+    pub elevator: Option<f64>,
+    pub rudder: Option<f64>,
+    pub roll_trim: Option<f64>,
+    pub pitch_trim: Option<f64>,
+    pub yaw_trim: Option<f64>,
+}
+
+/// Pilot head orientation in the cockpit relative to the aircraft
+/// orientation.  
+/// Unit: deg
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PilotHead {
+    pub roll: Option<f64>,
+    pub pitch: Option<f64>,
+    pub yaw: Option<f64>,
+}
+
+/// Pilot physiology, and engine noise level for gliders.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Physiology {
+    /// Unit: beats per minute
+    pub heart_rate: Option<u64>,
+    /// Blood oxygen saturation.  
+    /// Unit: ratio
+    pub spo2: Option<f64>,
+    /// Environmental noise level.  
+    /// Unit: ratio
+    pub enl: Option<f64>,
+}
+
+/// Fuel system state. Tanks and engines are indexed from 0, so
+/// `tank_weights[3]` holds `FuelWeight4`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FuelState {
+    /// Unit: kg
+    pub tank_weights: [Option<f64>; 9],
+    /// Unit: l
+    pub tank_volumes: [Option<f64>; 9],
+    /// Unit: kg/hour
+    pub flow_weights: [Option<f64>; 7],
+    /// Unit: l/hour
+    pub flow_volumes: [Option<f64>; 7],
+}
+
+impl fmt::Display for ObjectState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+
+        if let Some(name) = self.name() {
+            parts.push(name.to_string());
+        }
+        if let Some(callsign) = self.callsign() {
+            parts.push(format!("\"{callsign}\""));
+        }
+
+        let coords = self.coords();
+        let heading = find_property!(self, Hdg)
+            .copied()
+            .or_else(|| coords.and_then(|coords| coords.heading.or(coords.yaw)));
+        if let Some(heading) = heading {
+            parts.push(format!("HDG={heading:.0}"));
+        }
+        if let Some(altitude) = coords.and_then(|coords| coords.altitude) {
+            parts.push(format!("ALT={altitude:.0}m"));
+        }
+        if let Some(ias) = find_property!(self, Ias) {
+            parts.push(format!("IAS={:.0}kt", ias * KNOTS_PER_METER_PER_SECOND));
+        }
+
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_properties() {
+        let mut state = ObjectState::default();
+        for property in ["T=1|2|3", "Name=F-16C-52", "T=||4", "HDG=185", "HDG=190"] {
+            state.update(ObjectProperty::from_str(property).unwrap());
+        }
+
+        let mut properties: Vec<_> = state.properties().map(ToString::to_string).collect();
+        properties.sort();
+        assert_eq!(properties, ["HDG=190", "Name=F-16C-52", "T=1|2|4"]);
+
+        let mut names: Vec<_> = (&state).into_iter().map(ObjectProperty::name).collect();
+        names.sort();
+        assert_eq!(names, ["HDG", "Name", "T"]);
+    }
+
+    #[test]
+    fn test_summary() {
+        let mut state = ObjectState::default();
+        for property in [
+            "Name=F-16C-52",
+            "T=1.5|2.5|1500.2",
+            "HDG=185.3",
+            "IAS=69.4444",
+        ] {
+            state.update(ObjectProperty::from_str(property).unwrap());
+        }
+        state.update(ObjectProperty::Callsign("Jester".to_string()));
+        assert_eq!(
+            state.summary(),
+            "F-16C-52 \"Jester\" HDG=185 ALT=1500m IAS=135kt"
+        );
+
+        let mut state = ObjectState::default();
+        state.update(ObjectProperty::from_str("T=1|2|").unwrap());
+        state.update(ObjectProperty::from_str("T=||300").unwrap());
+        assert_eq!(state.coords().unwrap().longitude, Some(1.0));
+        assert_eq!(state.summary(), "ALT=300m");
+    }
+
+    #[test]
+    fn test_grouped_axes() {
+        let mut state = ObjectState::default();
+        assert_eq!(state.gforce(), None);
+        assert_eq!(state.control_inputs(), None);
+
+        for property in [
+            "VerticalGForce=3.4",
+            "LateralGForce=-0.2",
+            "PitchControlInput=0.41",
+            "RollControlPosition=0.1",
+            "PitchControlPosition=0.3",
+            "YawControlPosition=0",
+        ] {
+            state.update(ObjectProperty::from_str(property).unwrap());
+        }
+        assert_eq!(
+            state.gforce(),
+            Some(GForce {
+                vertical: Some(3.4),
+                longitudinal: None,
+                lateral: Some(-0.2),
+            })
+        );
+        assert_eq!(
+            state.control_inputs(),
+            Some(Axes {
+                roll: None,
+                pitch: Some(0.41),
+                yaw: None,
+            })
+        );
+        assert_eq!(
+            state.control_positions(),
+            Some(Axes {
+                roll: Some(0.1),
+                pitch: Some(0.3),
+                yaw: Some(0.0),
+            })
+        );
+    }
+
+    #[test]
+    fn test_radar() {
+        let mut state = ObjectState::default();
+        assert_eq!(state.radar(), RadarState::default());
+
+        for property in [
+            "RadarMode=1",
+            "RadarAzimuth=-20",
+            "RadarRange=296320",
+            "RadarRangeGateMin=37040",
+            "RadarRangeGateMax=74080",
+        ] {
+            state.update(ObjectProperty::from_str(property).unwrap());
+        }
+        assert_eq!(
+            state.radar(),
+            RadarState {
+                mode: Some(1),
+                azimuth: Some(-20.0),
+                range: Some(296320.0),
+                range_gate_min: Some(37040.0),
+                range_gate_max: Some(74080.0),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_fuel() {
+        let mut state = ObjectState::default();
+        for property in [
+            "FuelWeight=3000",
+            "FuelWeight4=8750",
+            "FuelVolume9=75",
+            "FuelFlowWeight2=38.08",
+            "FuelFlowVolume7=53.2",
+        ] {
+            state.update(ObjectProperty::from_str(property).unwrap());
+        }
+
+        let fuel = state.fuel();
+        assert_eq!(fuel.tank_weights[0], Some(3000.0));
+        assert_eq!(fuel.tank_weights[3], Some(8750.0));
+        assert_eq!(fuel.tank_weights.iter().flatten().count(), 2);
+        assert_eq!(fuel.tank_volumes[8], Some(75.0));
+        assert_eq!(fuel.tank_volumes.iter().flatten().count(), 1);
+        assert_eq!(fuel.flow_weights[1], Some(38.08));
+        assert_eq!(fuel.flow_volumes[6], Some(53.2));
+    }
+
+    #[test]
+    fn test_engagement_envelopes() {
+        let mut state = ObjectState::default();
+        assert!(state.engagement_envelopes().is_empty());
+        for property in [
+            "EngagementRange=2500",
+            "VerticalEngagementRange=1800",
+            "EngagementMode2=0",
+            "EngagementRange2=40000",
+        ] {
+            state.update(ObjectProperty::from_str(property).unwrap());
+        }
+
+        assert_eq!(
+            state.engagement_envelopes(),
+            vec![
+                Envelope {
+                    active: true,
+                    range: 2500.0,
+                    vertical_range: 1800.0,
+                },
+                Envelope {
+                    active: false,
+                    range: 40000.0,
+                    vertical_range: 40000.0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_age_of() {
+        let mut state = ObjectState::default();
+        state.update_at(ObjectProperty::from_str("T=1|2|3").unwrap(), 1.0);
+        state.update_at(ObjectProperty::from_str("Name=F-16C-52").unwrap(), 1.0);
+        assert_eq!(state.age_of("T", 1.5), Some(0.5));
+
+        state.update_at(ObjectProperty::from_str("T=||4").unwrap(), 3.0);
+        assert_eq!(state.age_of("T", 4.0), Some(1.0));
+        assert_eq!(state.age_of("Name", 4.0), Some(3.0));
+        assert_eq!(state.age_of("HDG", 4.0), None);
+    }
+
+    #[test]
+    fn test_physiology() {
+        let mut state = ObjectState::default();
+        assert_eq!(state.physiology(), None);
+        state.update(ObjectProperty::from_str("HeartRate=72").unwrap());
+        state.update(ObjectProperty::from_str("SpO2=0.95").unwrap());
+        assert_eq!(
+            state.physiology(),
+            Some(Physiology {
+                heart_rate: Some(72),
+                spo2: Some(0.95),
+                enl: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_controls() {
+        let mut state = ObjectState::default();
+        assert_eq!(state.controls(), Controls::default());
+        for property in [
+            "AileronLeft=0.1",
+            "AileronRight=-0.1",
+            "Elevator=0.15",
+            "Rudder=0",
+            "PitchTrimTab=-0.15",
+            "PilotHeadYaw=30",
+            "PilotHeadPitch=12",
+        ] {
+            state.update(ObjectProperty::from_str(property).unwrap());
+        }
+        assert_eq!(
+            state.controls(),
+            Controls {
+                aileron_left: Some(0.1),
+                aileron_right: Some(-0.1),
+                elevator: Some(0.15),
+                rudder: Some(0.0),
+                roll_trim: None,
+                pitch_trim: Some(-0.15),
+                yaw_trim: None,
+            }
+        );
+        assert_eq!(
+            state.pilot_head(),
+            PilotHead {
+                roll: None,
+                pitch: Some(12.0),
+                yaw: Some(30.0),
+            }
+        );
+    }
+
+    #[test]
+    fn test_modes() {
+        let mut state = ObjectState::default();
+        assert_eq!(state.radar_mode(), None);
+        state.update(ObjectProperty::from_str("RadarMode=0").unwrap());
+        state.update(ObjectProperty::from_str("LockedTargetMode=1").unwrap());
+        state.update(ObjectProperty::from_str("EngagementMode=1").unwrap());
+        state.update(ObjectProperty::from_str("EngagementMode2=3").unwrap());
+        assert_eq!(state.radar_mode(), Some(RadarMode::Off));
+        assert_eq!(state.locked_target_mode(), Some(LockMode::On));
+        assert_eq!(state.engagement_mode(), Some(EngagementMode::On));
+        assert_eq!(state.engagement_mode2(), Some(EngagementMode::Unknown(3)));
+        assert!(matches!(find_property!(state, EngagementMode2), Some(3)));
+    }
+}
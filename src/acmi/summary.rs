@@ -0,0 +1,111 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+
+use super::record::{object_property::ObjectProperty, Record};
+
+/// Aggregates of a whole recording, see [`summarize`].
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Summary {
+    /// Number of distinct object ids updated.
+    pub object_count: usize,
+    /// Time of the first frame.
+    pub start: Option<f64>,
+    /// Time of the last frame.
+    pub end: Option<f64>,
+    /// Number of events by [type](super::record::event::Event::name).
+    pub event_counts: BTreeMap<String, usize>,
+    /// Every coalition any object belonged to.
+    pub coalitions: BTreeSet<String>,
+}
+
+impl Summary {
+    /// Seconds between the first and the last frame.
+    pub fn duration(&self) -> f64 {
+        match (self.start, self.end) {
+            (Some(start), Some(end)) => end - start,
+            _ => 0.0,
+        }
+    }
+}
+
+/// Scans every record once, e.g. from a
+/// [`FileReader`](super::file::FileReader), and returns its [`Summary`].
+/// Fails on the first record which fails to parse.
+pub fn summarize<I>(records: I) -> Result<Summary>
+where
+    I: IntoIterator<Item = Result<Record>>,
+{
+    let mut summary = Summary::default();
+    let mut ids = HashSet::new();
+    for record in records {
+        match record? {
+            Record::Frame(time) => {
+                summary.start.get_or_insert(time);
+                summary.end = Some(time);
+            }
+            Record::Event(event) => {
+                *summary
+                    .event_counts
+                    .entry(event.name().to_string())
+                    .or_default() += 1;
+            }
+            Record::Update(id, object_properties) => {
+                ids.insert(id);
+                for object_property in object_properties {
+                    if let ObjectProperty::Coalition(coalition) = object_property {
+                        summary.coalitions.insert(coalition.to_string());
+                    }
+                }
+            }
+            Record::Remove(_) | Record::GlobalProperties(_) => {}
+        }
+    }
+    summary.object_count = ids.len();
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::acmi::file::FileReader;
+
+    const MISSION: &str = "FileType=text/acmi/tacview
+FileVersion=2.2
+0,ReferenceTime=2011-06-02T05:00:00Z
+#12.5
+A,T=1|2|3,Coalition=Enemies
+B,T=4|5|6,Coalition=Allies
+0,Event=Destroyed|B|
+#20
+C,T=7|8|9,Coalition=Allies
+0,Event=Bookmark|halfway
+0,Event=Destroyed|C|
+-C
+#42.5
+A,T=||400
+";
+
+    #[test]
+    fn test_summarize() {
+        let reader = FileReader::try_from_reader(Cursor::new(MISSION)).unwrap();
+        let summary = summarize(reader).unwrap();
+        assert_eq!(summary.object_count, 3);
+        assert_eq!(summary.start, Some(12.5));
+        assert_eq!(summary.end, Some(42.5));
+        assert_eq!(summary.duration(), 30.0);
+        assert_eq!(
+            summary.event_counts,
+            BTreeMap::from([("Bookmark".to_string(), 1), ("Destroyed".to_string(), 2)])
+        );
+        assert_eq!(
+            summary.coalitions,
+            BTreeSet::from(["Allies".to_string(), "Enemies".to_string()])
+        );
+    }
+}
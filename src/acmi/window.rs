@@ -0,0 +1,151 @@
+use std::collections::VecDeque;
+
+use crate::error::Result;
+
+use super::{record::Record, world::World};
+
+/// Keeps the records between the frame times `start` and `end`, inclusive,
+/// e.g. from a [`FileReader`](super::file::FileReader) whose header has
+/// already been read.
+///
+/// The window begins with a snapshot at its first frame: the global
+/// properties and the last known state of every object spawned before it,
+/// as if the recording started there. Events before the window are dropped.
+/// See [`Window::with_snapshot`] to only filter by time.
+pub fn parse_window<I>(records: I, start: f64, end: f64) -> Window<I::IntoIter>
+where
+    I: IntoIterator<Item = Result<Record>>,
+{
+    Window {
+        records: records.into_iter(),
+        start,
+        end,
+        snapshot: true,
+        before: Some(World::new()),
+        pending: VecDeque::new(),
+        ended: false,
+    }
+}
+
+/// Iterator returned by [`parse_window`].
+#[derive(Debug)]
+pub struct Window<I> {
+    records: I,
+    start: f64,
+    end: f64,
+    snapshot: bool,
+    /// State accumulated before the window, until it begins.
+    before: Option<World>,
+    pending: VecDeque<Record>,
+    ended: bool,
+}
+
+impl<I> Window<I> {
+    /// Begins the window with a snapshot of the state before it, enabled by
+    /// default. Without it, records before the window are dropped.
+    pub fn with_snapshot(mut self, snapshot: bool) -> Self {
+        self.snapshot = snapshot;
+        self
+    }
+}
+
+impl<I> Iterator for Window<I>
+where
+    I: Iterator<Item = Result<Record>>,
+{
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(record) = self.pending.pop_front() {
+                return Some(Ok(record));
+            }
+            if self.ended {
+                return None;
+            }
+
+            let record = match self.records.next()? {
+                Ok(record) => record,
+                Err(e) => return Some(Err(e)),
+            };
+            if let Record::Frame(time) = record {
+                if time > self.end {
+                    self.ended = true;
+                    continue;
+                }
+            }
+
+            let Some(before) = &mut self.before else {
+                return Some(Ok(record));
+            };
+            match record {
+                Record::Frame(time) if time >= self.start => {
+                    let mut before = self.before.take().unwrap();
+                    if self.snapshot {
+                        before.apply(record);
+                        self.pending.extend(before.snapshot());
+                    } else {
+                        self.pending.push_back(record);
+                    }
+                }
+                record => {
+                    if self.snapshot {
+                        before.apply(record);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+
+    const MISSION: &[&str] = &[
+        "0,ReferenceLongitude=-129",
+        "#0",
+        "A,T=1|2|3,Name=F-16C-52",
+        "B,T=4|5|6",
+        "#5",
+        "A,T=||300",
+        "0,Event=Bookmark|before",
+        "-B",
+        "#10",
+        "C,T=7|8|9",
+        "#15",
+        "A,T=||400",
+        "#20",
+        "-A",
+    ];
+
+    fn window(start: f64, end: f64, snapshot: bool) -> Vec<String> {
+        let records = MISSION.iter().map(|line| Record::from_str(line));
+        parse_window(records, start, end)
+            .with_snapshot(snapshot)
+            .map(|record| record.unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_window() {
+        assert_eq!(
+            window(7.0, 15.0, true),
+            vec![
+                "0,ReferenceLongitude=-129",
+                "#10",
+                "A,T=1|2|300,Name=F-16C-52",
+                "C,T=7|8|9",
+                "#15",
+                "A,T=||400",
+            ]
+        );
+        assert_eq!(
+            window(7.0, 15.0, false),
+            vec!["#10", "C,T=7|8|9", "#15", "A,T=||400"]
+        );
+        assert!(window(21.0, 30.0, true).is_empty());
+    }
+}
@@ -0,0 +1,1529 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt,
+    io::Write,
+    sync::Arc,
+};
+
+use time::OffsetDateTime;
+
+use crate::error::{Error, Result};
+
+use super::{
+    record::{
+        event::Event,
+        global_property::GlobalProperty,
+        object_property::{
+            meters_to_feet, meters_to_nautical_miles, normalize_heading, Coords, ObjectProperty,
+            Tag,
+        },
+        PropertyKey, Record, Warning,
+    },
+    state::{Metadata, ObjectState},
+    Header,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WorldEvent {
+    /// `ReferenceTime`, `ReferenceLongitude` or `ReferenceLatitude` has been
+    /// resent with a different value. Object coordinates and frame times are
+    /// relative to these references, so any absolute coordinate or time
+    /// computed before this event is invalid and should be recomputed. The
+    /// stored object coordinates are kept as received.
+    ReferenceChanged {
+        old: GlobalProperty,
+        new: GlobalProperty,
+    },
+    /// The `Type` of an object has changed. `old` is empty when the type is
+    /// set for the first time.
+    TypeChanged {
+        id: u64,
+        old: HashSet<Tag>,
+        new: HashSet<Tag>,
+    },
+    /// The stream has been restarted with a different header, e.g. after a
+    /// reconnection.
+    HeaderChanged(Header),
+    /// `RecordingTime` or `Title` has changed, meaning a different mission is
+    /// being streamed. Every object and global property of the previous
+    /// mission has been cleared, so consumers should clear their views too.
+    MissionChanged,
+    /// A property has been sent more than once in a single update. The last
+    /// value wins. Only reported when enabled with
+    /// [`with_duplicate_reports`](World::with_duplicate_reports), such updates
+    /// can be rejected instead with
+    /// [`Record::from_str_rejecting_duplicates`].
+    DuplicateProperty { id: u64, property: ObjectProperty },
+    /// The `Health` of an object has dropped to 0 for the first time, which
+    /// usually means it has been destroyed even without a `Destroyed` event.
+    /// Not sent again if the object recovers and is depleted again.
+    HealthDepleted { id: u64 },
+    /// Properties of an object which have genuinely changed in an update,
+    /// with unchanged coordinate components removed. Only sent when enabled
+    /// with [`with_deduplication`](World::with_deduplication).
+    PropertiesChanged {
+        id: u64,
+        properties: Vec<ObjectProperty>,
+    },
+    /// A frame time is smaller than the previous one, e.g. a server starting
+    /// a sub-mission over from `#0` in the same stream. Consumers should start
+    /// a new timeline segment. Objects are kept, and [absolute
+    /// times](World::absolute_time) still add the frame time to the same
+    /// `ReferenceTime`, so they go back in time too.
+    TimeReset { previous: f64, time: f64 },
+    /// A spawned object is likely the continuation of a removed one, e.g. a
+    /// jettisoned store taking over a new id. Only sent when enabled with
+    /// [`with_reidentification`](World::with_reidentification).
+    Reidentified { old: u64, new: u64 },
+}
+
+/// Object removed recently enough to be [reidentified](WorldEvent::Reidentified).
+#[derive(Debug, Clone)]
+struct RemovedObject {
+    id: u64,
+    time: f64,
+    name: String,
+    coords: Coords,
+}
+
+/// Position of an object at a point in time, see
+/// [`World::with_track_history`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackSample {
+    pub time: f64,
+    pub coords: Coords,
+    /// Unit: m
+    pub radius: Option<f64>,
+}
+
+/// Number of intervals between frames averaged by [`World::frame_rate_hz`].
+const FRAME_RATE_WINDOW: usize = 16;
+
+type ResolveFn = dyn Fn(&str, &HashSet<Tag>) -> Option<Metadata> + Send + Sync;
+
+#[derive(Clone)]
+struct NameResolver(Arc<ResolveFn>);
+
+impl fmt::Debug for NameResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("NameResolver")
+    }
+}
+
+/// An object removed from the [`World`], see
+/// [`World::with_retain_destroyed`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DestroyedObject {
+    /// Frame time of the removal.
+    pub time: f64,
+    /// Last state before the removal.
+    pub state: ObjectState,
+    /// Full track history, oldest first.
+    pub track: Vec<TrackSample>,
+}
+
+/// Accumulated state of the whole battlefield, built by applying every record
+/// read from a stream in order.
+///
+/// Objects are removed by `Remove` records, which mean the object no longer
+/// exists in the stream. `Destroyed` and `LeftArea` events are only reports:
+/// a destroyed object may keep sending updates (e.g. as a wreck), so they
+/// remove objects only when enabled with
+/// [`with_event_removals`](Self::with_event_removals). An update for a
+/// removed id starts a new object.
+///
+/// Object longitudes and latitudes are relative to `ReferenceLongitude` and
+/// `ReferenceLatitude`. Tacview sends them in the first global properties
+/// record, before any object, but other exporters may send objects first.
+/// Coordinates received before any reference are considered absolute, unless
+/// re-offset when the reference arrives with
+/// [`with_late_reference_reoffset`](Self::with_late_reference_reoffset).
+#[derive(Debug, Clone, Default)]
+pub struct World {
+    event_removals: bool,
+    duplicate_reports: bool,
+    visibility_threshold: f64,
+    deduplication: Option<f64>,
+    late_reference_reoffset: bool,
+    track_capacity: usize,
+    validation: bool,
+    retain_destroyed: bool,
+    reidentification: Option<(f64, f64)>,
+    name_resolver: Option<NameResolver>,
+    header: Option<Header>,
+    has_frame: bool,
+    time: f64,
+    frame_intervals: VecDeque<f64>,
+    global_properties: HashMap<PropertyKey<GlobalProperty>, GlobalProperty>,
+    objects: HashMap<u64, ObjectState>,
+    objects_by_tag: HashMap<Tag, HashSet<u64>>,
+    frame_updates: HashSet<u64>,
+    frame_spawns: HashSet<u64>,
+    health_depleted: HashSet<u64>,
+    unreferenced: HashSet<u64>,
+    tracks: HashMap<u64, VecDeque<TrackSample>>,
+    graveyard: HashMap<u64, DestroyedObject>,
+    recently_removed: Vec<RemovedObject>,
+    warnings: Vec<Warning>,
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also removes objects on `Destroyed` and `LeftArea` events.
+    pub fn with_event_removals(mut self, event_removals: bool) -> Self {
+        self.event_removals = event_removals;
+        self
+    }
+
+    /// Reports properties sent more than once in a single update with
+    /// [`WorldEvent::DuplicateProperty`].
+    pub fn with_duplicate_reports(mut self, duplicate_reports: bool) -> Self {
+        self.duplicate_reports = duplicate_reports;
+        self
+    }
+
+    /// Objects are visible when their `Visible` ratio is greater than the
+    /// threshold, `0` by default. Objects which never sent `Visible` are always
+    /// visible.
+    pub fn with_visibility_threshold(mut self, visibility_threshold: f64) -> Self {
+        self.visibility_threshold = visibility_threshold;
+        self
+    }
+
+    /// Drops property values equal to the current ones when applying updates,
+    /// floats being compared with `epsilon`, and reports the remaining ones
+    /// with [`WorldEvent::PropertiesChanged`]. Objects whose update changed
+    /// nothing are not part of the [`frame_updates`](Self::frame_updates).
+    pub fn with_deduplication(mut self, epsilon: f64) -> Self {
+        self.deduplication = Some(epsilon);
+        self
+    }
+
+    /// Applies the reference to coordinates received before it, as if it had
+    /// been sent first.
+    pub fn with_late_reference_reoffset(mut self, late_reference_reoffset: bool) -> Self {
+        self.late_reference_reoffset = late_reference_reoffset;
+        self
+    }
+
+    /// Keeps the last `capacity` positions and radiuses of each object,
+    /// sampled once per frame in which it moves or changes radius.
+    pub fn with_track_history(mut self, capacity: usize) -> Self {
+        self.track_capacity = capacity;
+        self
+    }
+
+    /// Keeps removed objects, with their last state and track, in a
+    /// graveyard accessible with [`destroyed`](Self::destroyed) instead of
+    /// purging them.
+    pub fn with_retain_destroyed(mut self, retain_destroyed: bool) -> Self {
+        self.retain_destroyed = retain_destroyed;
+        self
+    }
+
+    /// Reports [`WorldEvent::Reidentified`] when an object spawns, in its
+    /// first update, with the same `Name` as an object removed at most
+    /// `max_delay` seconds before, and at most `max_distance` meters
+    /// horizontally from its last position. The closest match wins.
+    pub fn with_reidentification(mut self, max_distance: f64, max_delay: f64) -> Self {
+        self.reidentification = Some((max_distance, max_delay));
+        self
+    }
+
+    /// Validates every applied record with [`Record::validate`], and events
+    /// against the known objects, collecting the
+    /// [`warnings`](Self::take_warnings).
+    pub fn with_validation(mut self, validation: bool) -> Self {
+        self.validation = validation;
+        self
+    }
+
+    /// Resolves the [`Metadata`] of objects from an external database. The
+    /// resolver is called with the name and tags of an object when an update
+    /// brings its `Name` or `Type`, until it returns some metadata, which is
+    /// then cached on the [`ObjectState`].
+    pub fn set_name_resolver<F>(&mut self, resolver: F)
+    where
+        F: Fn(&str, &HashSet<Tag>) -> Option<Metadata> + Send + Sync + 'static,
+    {
+        self.name_resolver = Some(NameResolver(Arc::new(resolver)));
+    }
+
+    /// Returns and clears the warnings collected since the last call.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Applies a record, returning the events it caused.
+    pub fn apply(&mut self, record: Record) -> Vec<WorldEvent> {
+        if self.validation {
+            self.validate(&record);
+        }
+
+        let mut events = Vec::new();
+        match record {
+            Record::Remove(id) => {
+                self.remove_object(id);
+            }
+            Record::Frame(time) => {
+                if self.has_frame && time < self.time {
+                    events.push(WorldEvent::TimeReset {
+                        previous: self.time,
+                        time,
+                    });
+                    self.frame_intervals.clear();
+                } else if self.has_frame && time > self.time {
+                    if self.frame_intervals.len() == FRAME_RATE_WINDOW {
+                        self.frame_intervals.pop_front();
+                    }
+                    self.frame_intervals.push_back(time - self.time);
+                }
+                self.has_frame = true;
+                self.time = time;
+                self.frame_updates.clear();
+                self.frame_spawns.clear();
+            }
+            Record::Event(Event::Destroyed(id) | Event::LeftArea(id)) if self.event_removals => {
+                self.remove_object(id);
+            }
+            Record::Event(_) => {}
+            Record::GlobalProperties(global_properties) => {
+                let is_new_mission = global_properties.iter().any(|global_property| {
+                    matches!(
+                        global_property,
+                        GlobalProperty::RecordingTime(_) | GlobalProperty::Title(_)
+                    ) && self
+                        .global_properties
+                        .get(&global_property.key())
+                        .is_some_and(|current| current != global_property)
+                });
+                if is_new_mission {
+                    self.reset();
+                    events.push(WorldEvent::MissionChanged);
+                }
+
+                let has_reference = global_properties.iter().any(|global_property| {
+                    matches!(
+                        global_property,
+                        GlobalProperty::ReferenceLongitude(_)
+                            | GlobalProperty::ReferenceLatitude(_)
+                    )
+                });
+                if has_reference && self.late_reference_reoffset {
+                    self.unreferenced.clear();
+                }
+
+                for global_property in global_properties {
+                    let is_reference = matches!(
+                        global_property,
+                        GlobalProperty::ReferenceTime(_)
+                            | GlobalProperty::ReferenceLongitude(_)
+                            | GlobalProperty::ReferenceLatitude(_)
+                    );
+                    let old = self
+                        .global_properties
+                        .insert(global_property.key(), global_property.clone());
+                    if let Some(old) = old {
+                        if is_reference && old != global_property {
+                            events.push(WorldEvent::ReferenceChanged {
+                                old,
+                                new: global_property,
+                            });
+                        }
+                    }
+                }
+            }
+            Record::Update(id, object_properties) => {
+                let has_reference = self.reference().is_some();
+                let spawned = !self.objects.contains_key(&id);
+                if spawned {
+                    self.frame_spawns.insert(id);
+                }
+                let object = self.objects.entry(id).or_default();
+                let mut seen = HashSet::new();
+                let mut changed = Vec::new();
+                let mut moved = false;
+                let mut identified = false;
+                for object_property in object_properties {
+                    if self.duplicate_reports && !seen.insert(object_property.key()) {
+                        events.push(WorldEvent::DuplicateProperty {
+                            id,
+                            property: object_property.clone(),
+                        });
+                    }
+                    let object_property = match self.deduplication {
+                        Some(epsilon) => match changed_part(object, object_property, epsilon) {
+                            Some(object_property) => {
+                                changed.push(object_property.clone());
+                                object_property
+                            }
+                            None => continue,
+                        },
+                        None => object_property,
+                    };
+                    moved |= matches!(
+                        object_property,
+                        ObjectProperty::T(_) | ObjectProperty::Radius(_)
+                    );
+                    identified |= matches!(
+                        object_property,
+                        ObjectProperty::Name(_) | ObjectProperty::Type(_)
+                    );
+                    if let ObjectProperty::T(coords) = &object_property {
+                        if coords.longitude.is_some() || coords.latitude.is_some() {
+                            if has_reference {
+                                self.unreferenced.remove(&id);
+                            } else {
+                                self.unreferenced.insert(id);
+                            }
+                        }
+                    }
+                    if let ObjectProperty::Type(new) = &object_property {
+                        let old = object.tags().cloned().unwrap_or_default();
+                        if &old != new {
+                            for tag in old.difference(new) {
+                                if let Some(ids) = self.objects_by_tag.get_mut(tag) {
+                                    ids.remove(&id);
+                                }
+                            }
+                            for tag in new.difference(&old) {
+                                self.objects_by_tag
+                                    .entry(tag.clone())
+                                    .or_default()
+                                    .insert(id);
+                            }
+                            events.push(WorldEvent::TypeChanged {
+                                id,
+                                old,
+                                new: new.clone(),
+                            });
+                        }
+                    }
+                    object.update_at(object_property, self.time);
+                }
+                if let (true, Some(NameResolver(resolver)), None, Some(name)) = (
+                    identified,
+                    &self.name_resolver,
+                    object.metadata(),
+                    object.name(),
+                ) {
+                    let tags = object.tags().cloned().unwrap_or_default();
+                    let metadata = resolver(name, &tags);
+                    object.set_metadata(metadata);
+                }
+                if moved && self.track_capacity > 0 {
+                    let sample = TrackSample {
+                        time: self.time,
+                        coords: object.coords().cloned().unwrap_or_default(),
+                        radius: object.radius_m(),
+                    };
+                    let track = self.tracks.entry(id).or_default();
+                    if track.back().is_some_and(|last| last.time == sample.time) {
+                        track.pop_back();
+                    }
+                    if track.len() == self.track_capacity {
+                        track.pop_front();
+                    }
+                    track.push_back(sample);
+                }
+                if object.is_destroyed() && self.health_depleted.insert(id) {
+                    events.push(WorldEvent::HealthDepleted { id });
+                }
+                if self.deduplication.is_none() {
+                    self.frame_updates.insert(id);
+                } else if !changed.is_empty() {
+                    self.frame_updates.insert(id);
+                    events.push(WorldEvent::PropertiesChanged {
+                        id,
+                        properties: changed,
+                    });
+                }
+                if spawned {
+                    if let Some(old) = self.reidentify(id) {
+                        events.push(WorldEvent::Reidentified { old, new: id });
+                    }
+                }
+            }
+        }
+        events
+    }
+
+    /// Finds the removed object the spawned `id` continues, see
+    /// [`with_reidentification`](Self::with_reidentification).
+    fn reidentify(&mut self, id: u64) -> Option<u64> {
+        let (max_distance, max_delay) = self.reidentification?;
+        let time = self.time;
+        self.recently_removed
+            .retain(|removed| (0.0..=max_delay).contains(&(time - removed.time)));
+        let name = self.objects.get(&id)?.name()?;
+        let coords = self.absolute_coords(id)?;
+        let (index, _) = self
+            .recently_removed
+            .iter()
+            .enumerate()
+            .filter(|(_, removed)| removed.name == name)
+            .filter_map(|(index, removed)| {
+                let (_, range) = removed.coords.bearing_range_to(&coords)?;
+                (range <= max_distance).then_some((index, range))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))?;
+        Some(self.recently_removed.swap_remove(index).id)
+    }
+
+    fn validate(&mut self, record: &Record) {
+        self.warnings.extend(record.validate());
+        if let Record::Event(
+            Event::Message(id, _)
+            | Event::LeftArea(id)
+            | Event::Destroyed(id)
+            | Event::TakenOff(id, _)
+            | Event::Landed(id, _),
+        ) = record
+        {
+            if !self.objects.contains_key(id) {
+                self.warnings.push(Warning::UnknownObject { id: *id });
+            }
+        }
+    }
+
+    /// Sets the header of the stream, e.g. after a reconnection. Returns
+    /// [`WorldEvent::HeaderChanged`] if it differs from the previous one.
+    pub fn set_header(&mut self, header: Header) -> Vec<WorldEvent> {
+        let old = self.header.replace(header.clone());
+        if old.is_some_and(|old| old != header) {
+            vec![WorldEvent::HeaderChanged(header)]
+        } else {
+            Vec::new()
+        }
+    }
+
+    pub fn header(&self) -> Option<&Header> {
+        self.header.as_ref()
+    }
+
+    /// Records recreating the current state from scratch: the global
+    /// properties sorted by name, the current frame, then every object by
+    /// ascending id, with its coordinates first and other properties by name.
+    pub(crate) fn snapshot(&self) -> Vec<Record> {
+        let mut records = Vec::with_capacity(self.objects.len() + 2);
+        let mut global_properties: Vec<_> = self.global_properties().cloned().collect();
+        global_properties.sort_by_cached_key(ToString::to_string);
+        if !global_properties.is_empty() {
+            records.push(Record::GlobalProperties(global_properties));
+        }
+        records.push(Record::Frame(self.time));
+        let mut objects: Vec<_> = self.objects().collect();
+        objects.sort_unstable_by_key(|(id, _)| *id);
+        records.extend(objects.into_iter().map(|(id, object)| {
+            let mut properties: Vec<_> = object.properties().cloned().collect();
+            properties.sort_by(|a, b| {
+                let is_coords = |p| matches!(p, &ObjectProperty::T(_));
+                (!is_coords(a), a.name()).cmp(&(!is_coords(b), b.name()))
+            });
+            Record::Update(id, properties)
+        }));
+        records
+    }
+
+    /// Writes the current state as a self-contained ACMI file: the header,
+    /// or the default one when unknown, the global properties, the current
+    /// frame, then every object by ascending id with its merged properties.
+    pub fn write_snapshot<W: Write>(&self, mut writer: W) -> Result<()> {
+        let (file_type, file_version) = match &self.header {
+            Some(header) => (header.file_type.as_str(), header.file_version.as_str()),
+            None => ("text/acmi/tacview", "2.2"),
+        };
+        writeln!(writer, "FileType={file_type}").map_err(Error::AcmiWriterWrite)?;
+        writeln!(writer, "FileVersion={file_version}").map_err(Error::AcmiWriterWrite)?;
+        for record in self.snapshot() {
+            writeln!(writer, "{record}").map_err(Error::AcmiWriterWrite)?;
+        }
+        writer.flush().map_err(Error::AcmiWriterWrite)
+    }
+
+    /// Clears every object and global property, keeping the options and the
+    /// header.
+    fn reset(&mut self) {
+        *self = Self {
+            event_removals: self.event_removals,
+            duplicate_reports: self.duplicate_reports,
+            visibility_threshold: self.visibility_threshold,
+            deduplication: self.deduplication,
+            late_reference_reoffset: self.late_reference_reoffset,
+            track_capacity: self.track_capacity,
+            validation: self.validation,
+            retain_destroyed: self.retain_destroyed,
+            reidentification: self.reidentification,
+            name_resolver: self.name_resolver.take(),
+            header: self.header.take(),
+            ..Default::default()
+        };
+    }
+
+    fn remove_object(&mut self, id: u64) {
+        if self.reidentification.is_some() {
+            let name = self.object(id).and_then(ObjectState::name);
+            if let (Some(name), Some(coords)) = (name, self.absolute_coords(id)) {
+                self.recently_removed.push(RemovedObject {
+                    id,
+                    time: self.time,
+                    name: name.to_string(),
+                    coords,
+                });
+            }
+        }
+        let track = self.tracks.remove(&id);
+        if let Some(object) = self.objects.remove(&id) {
+            for tag in object.tags().into_iter().flatten() {
+                if let Some(ids) = self.objects_by_tag.get_mut(tag) {
+                    ids.remove(&id);
+                }
+            }
+            if self.retain_destroyed {
+                let destroyed = DestroyedObject {
+                    time: self.time,
+                    state: object,
+                    track: track.map(Vec::from).unwrap_or_default(),
+                };
+                self.graveyard.insert(id, destroyed);
+            }
+        }
+        self.frame_updates.remove(&id);
+        self.frame_spawns.remove(&id);
+        self.health_depleted.remove(&id);
+        self.unreferenced.remove(&id);
+    }
+
+    /// Time offset of the current frame, in seconds.
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// Rate of incoming frames, averaged over the intervals between the last
+    /// few frames so it adapts to pauses. `None` until two frames with
+    /// different times were applied.
+    pub fn frame_rate_hz(&self) -> Option<f64> {
+        if self.frame_intervals.is_empty() {
+            return None;
+        }
+        let total: f64 = self.frame_intervals.iter().sum();
+        Some(self.frame_intervals.len() as f64 / total)
+    }
+
+    /// `ReferenceTime` plus the [time offset](Self::time) of the current
+    /// frame, if the reference time is known and the sum is representable.
+    pub fn absolute_time(&self) -> Option<OffsetDateTime> {
+        self.global_properties()
+            .find_map(|global_property| match global_property {
+                GlobalProperty::ReferenceTime(reference_time) => Some(*reference_time),
+                _ => None,
+            })
+            .and_then(|reference_time| {
+                reference_time.checked_add(time::Duration::checked_seconds_f64(self.time)?)
+            })
+    }
+
+    pub fn global_properties(&self) -> impl Iterator<Item = &GlobalProperty> {
+        self.global_properties.values()
+    }
+
+    pub fn object(&self, id: u64) -> Option<&ObjectState> {
+        self.objects.get(&id)
+    }
+
+    /// `(ReferenceLongitude, ReferenceLatitude)`, if any of them has been
+    /// received. The missing one is 0.
+    pub fn reference(&self) -> Option<(f64, f64)> {
+        let mut longitude = None;
+        let mut latitude = None;
+        for global_property in self.global_properties.values() {
+            match global_property {
+                GlobalProperty::ReferenceLongitude(value) => longitude = Some(*value),
+                GlobalProperty::ReferenceLatitude(value) => latitude = Some(*value),
+                _ => {}
+            }
+        }
+        (longitude.is_some() || latitude.is_some())
+            .then(|| (longitude.unwrap_or_default(), latitude.unwrap_or_default()))
+    }
+
+    /// Whether the [`absolute_coords`](Self::absolute_coords) of the object
+    /// are offset by the reference. `false` for objects positioned before
+    /// any reference was received.
+    pub fn is_reference_adjusted(&self, id: u64) -> bool {
+        self.objects.contains_key(&id)
+            && self.reference().is_some()
+            && !self.unreferenced.contains(&id)
+    }
+
+    /// Coordinates of the object, with the reference added to its longitude
+    /// and latitude when [adjusted](Self::is_reference_adjusted).
+    pub fn absolute_coords(&self, id: u64) -> Option<Coords> {
+        let mut coords = self.objects.get(&id)?.coords()?.clone();
+        if let Some((longitude, latitude)) =
+            self.reference().filter(|_| self.is_reference_adjusted(id))
+        {
+            coords.longitude = coords.longitude.map(|value| value + longitude);
+            coords.latitude = coords.latitude.map(|value| value + latitude);
+        }
+        Some(coords)
+    }
+
+    pub fn object_count(&self) -> usize {
+        self.objects.len()
+    }
+
+    /// Removed objects, by id, when enabled with
+    /// [`with_retain_destroyed`](Self::with_retain_destroyed). An id reused
+    /// after its removal only keeps its last removed object.
+    pub fn destroyed(&self) -> impl Iterator<Item = (u64, &DestroyedObject)> {
+        self.graveyard.iter().map(|(id, object)| (*id, object))
+    }
+
+    /// Past positions of the object, oldest first. Empty unless enabled with
+    /// [`with_track_history`](Self::with_track_history).
+    pub fn track(&self, id: u64) -> impl Iterator<Item = &TrackSample> {
+        self.tracks.get(&id).into_iter().flatten()
+    }
+
+    /// Number of track samples of every object.
+    pub fn track_sample_count(&self) -> usize {
+        self.tracks.values().map(VecDeque::len).sum()
+    }
+
+    /// Approximate memory used by the world, in bytes, to watch the footprint
+    /// of long-running sessions. Strings and tag sets are not accounted for.
+    pub fn estimated_bytes(&self) -> usize {
+        let objects: usize = self
+            .objects
+            .values()
+            .map(ObjectState::estimated_bytes)
+            .sum::<usize>()
+            + self.objects.capacity() * std::mem::size_of::<(u64, ObjectState)>();
+        let tags: usize = self
+            .objects_by_tag
+            .values()
+            .map(|ids| ids.capacity() * std::mem::size_of::<u64>())
+            .sum::<usize>()
+            + self.objects_by_tag.capacity() * std::mem::size_of::<(Tag, HashSet<u64>)>();
+        let tracks: usize = self
+            .tracks
+            .values()
+            .map(|track| track.capacity() * std::mem::size_of::<TrackSample>())
+            .sum::<usize>()
+            + self.tracks.capacity() * std::mem::size_of::<(u64, VecDeque<TrackSample>)>();
+        let global_properties = self.global_properties.capacity()
+            * std::mem::size_of::<(PropertyKey<GlobalProperty>, GlobalProperty)>();
+        let ids = (self.frame_updates.capacity()
+            + self.frame_spawns.capacity()
+            + self.health_depleted.capacity()
+            + self.unreferenced.capacity())
+            * std::mem::size_of::<u64>();
+        std::mem::size_of::<Self>() + objects + tags + tracks + global_properties + ids
+    }
+
+    pub fn objects(&self) -> impl Iterator<Item = (u64, &ObjectState)> {
+        self.objects.iter().map(|(id, object)| (*id, object))
+    }
+
+    /// Whether the object exists and is not hidden by its `Visible` property.
+    /// See [`with_visibility_threshold`](Self::with_visibility_threshold).
+    pub fn is_visible(&self, id: u64) -> bool {
+        self.objects
+            .get(&id)
+            .is_some_and(|object| self.is_object_visible(object))
+    }
+
+    /// Objects not hidden by their `Visible` property, for a fog-of-war view.
+    pub fn visible_objects(&self) -> impl Iterator<Item = (u64, &ObjectState)> {
+        self.objects()
+            .filter(|(_, object)| self.is_object_visible(object))
+    }
+
+    fn is_object_visible(&self, object: &ObjectState) -> bool {
+        object.visibility().unwrap_or(1.0) > self.visibility_threshold
+    }
+
+    /// Ids of the objects whose `Type` contains the tag.
+    pub fn objects_with_tag(&self, tag: &Tag) -> impl Iterator<Item = u64> + '_ {
+        self.objects_by_tag.get(tag).into_iter().flatten().copied()
+    }
+
+    /// Objects of the `Group`, by ascending `Slot`, objects without a slot
+    /// last. Ties are ordered by id.
+    pub fn group_members(&self, group: &str) -> Vec<&ObjectState> {
+        let mut members: Vec<_> = self
+            .objects()
+            .filter(|(_, object)| object.group() == Some(group))
+            .collect();
+        members.sort_unstable_by_key(|(id, object)| (object.slot().is_none(), object.slot(), *id));
+        members.into_iter().map(|(_, object)| object).collect()
+    }
+
+    /// Member of the `Group` with the lowest `Slot`, see
+    /// [`group_members`](Self::group_members).
+    pub fn group_leader(&self, group: &str) -> Option<&ObjectState> {
+        self.group_members(group).into_iter().next()
+    }
+
+    /// Objects which appeared in the current frame, including objects
+    /// removed earlier and added again with the same id.
+    pub fn frame_spawns(&self) -> impl Iterator<Item = (u64, &ObjectState)> {
+        self.frame_spawns
+            .iter()
+            .filter_map(|id| self.objects.get(id).map(|object| (*id, object)))
+    }
+
+    /// The bullseye object, of the given coalition if any. When several match,
+    /// the one with the lowest id is returned.
+    pub fn bullseye(&self, coalition: Option<&str>) -> Option<&ObjectState> {
+        self.objects.get(&self.bullseye_id(coalition)?)
+    }
+
+    fn bullseye_id(&self, coalition: Option<&str>) -> Option<u64> {
+        self.objects_with_tag(&Tag::Bullseye)
+            .filter(|id| {
+                coalition.is_none()
+                    || self.objects.get(id).and_then(ObjectState::coalition) == coalition
+            })
+            .min()
+    }
+
+    /// Position of the object from the [bullseye](Self::bullseye) of the
+    /// coalition, as `BRA <bearing>/<range>/<altitude>`: true bearing in
+    /// degrees, range in nautical miles, and altitude in feet, rounded. `None`
+    /// without bullseye or without coordinates.
+    pub fn bra_label(&self, id: u64, coalition: Option<&str>) -> Option<String> {
+        let bullseye = self.absolute_coords(self.bullseye_id(coalition)?)?;
+        let coords = self.absolute_coords(id)?;
+        let (bearing, range) = bullseye.bearing_range_to(&coords)?;
+        let bearing = normalize_heading(bearing.round());
+        Some(format!(
+            "BRA {bearing:03.0}/{:.0}/{:.0}",
+            meters_to_nautical_miles(range),
+            meters_to_feet(coords.altitude?),
+        ))
+    }
+
+    /// Objects updated in the current frame. Every update of an object within
+    /// the same frame is merged into a single snapshot.
+    pub fn frame_updates(&self) -> impl Iterator<Item = (u64, &ObjectState)> {
+        self.frame_updates
+            .iter()
+            .filter_map(|id| self.objects.get(id).map(|object| (*id, object)))
+    }
+}
+
+/// Strips the parts of `property` equal to the current value in `object`,
+/// returning `None` if nothing changed.
+fn changed_part(
+    object: &ObjectState,
+    property: ObjectProperty,
+    epsilon: f64,
+) -> Option<ObjectProperty> {
+    let unchanged = |new: f64, current: Option<f64>| {
+        current.is_some_and(|current| (new - current).abs() <= epsilon)
+    };
+
+    if let ObjectProperty::T(mut coords) = property {
+        let current = object.coords().cloned().unwrap_or_default();
+        for (new, current) in [
+            (&mut coords.longitude, current.longitude),
+            (&mut coords.latitude, current.latitude),
+            (&mut coords.altitude, current.altitude),
+            (&mut coords.roll, current.roll),
+            (&mut coords.pitch, current.pitch),
+            (&mut coords.yaw, current.yaw),
+            (&mut coords.u, current.u),
+            (&mut coords.v, current.v),
+            (&mut coords.heading, current.heading),
+        ] {
+            if new.is_some_and(|new| unchanged(new, current)) {
+                *new = None;
+            }
+        }
+        return (coords != Coords::default()).then_some(ObjectProperty::T(coords));
+    }
+
+    let current = object.property(&property.key());
+    let is_unchanged = match (property.as_f64(), current) {
+        (Some(new), Some(current)) => unchanged(new, current.as_f64()),
+        (None, Some(current)) => current == &property,
+        (_, None) => false,
+    };
+    (!is_unchanged).then_some(property)
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::acmi::record::UnknownValue;
+
+    #[test]
+    fn test_frame_updates() {
+        let mut world = World::new();
+        for line in [
+            "#1",
+            "A,T=1|2|3,Name=F-16C-52",
+            "B,T=4|5|6",
+            "A,T=||300|10|20|30",
+            "#2",
+            "B,T=7||",
+        ] {
+            world.apply(Record::from_str(line).unwrap());
+            if line == "A,T=||300|10|20|30" {
+                let updates: HashMap<_, _> = world.frame_updates().collect();
+                assert_eq!(updates.len(), 2);
+                let coords = updates[&0xA].coords().unwrap();
+                assert_eq!(coords.longitude, Some(1.0));
+                assert_eq!(coords.altitude, Some(300.0));
+                assert_eq!(coords.yaw, Some(30.0));
+                assert_eq!(updates[&0xA].name(), Some("F-16C-52"));
+            }
+        }
+
+        assert_eq!(world.time(), 2.0);
+        let updates: Vec<_> = world.frame_updates().collect();
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].0, 0xB);
+        assert_eq!(updates[0].1.coords().unwrap().longitude, Some(7.0));
+        assert_eq!(updates[0].1.coords().unwrap().latitude, Some(5.0));
+    }
+
+    #[test]
+    fn test_reference_changed() {
+        let mut world = World::new();
+        let events = world.apply(Record::from_str("0,ReferenceLongitude=-129,Author=A").unwrap());
+        assert!(events.is_empty());
+        let events = world.apply(Record::from_str("0,ReferenceLongitude=-129,Author=B").unwrap());
+        assert!(events.is_empty());
+
+        let events = world.apply(Record::from_str("0,ReferenceLongitude=-130").unwrap());
+        assert_eq!(
+            events,
+            vec![WorldEvent::ReferenceChanged {
+                old: GlobalProperty::ReferenceLongitude(-129.0),
+                new: GlobalProperty::ReferenceLongitude(-130.0),
+            }]
+        );
+        assert!(world
+            .global_properties()
+            .any(|property| property == &GlobalProperty::ReferenceLongitude(-130.0)));
+    }
+
+    #[test]
+    fn test_type_changed() {
+        let mut world = World::new();
+        let events = world.apply(Record::from_str("A,Type=Misc+Container").unwrap());
+        assert_eq!(
+            events,
+            vec![WorldEvent::TypeChanged {
+                id: 0xA,
+                old: HashSet::new(),
+                new: HashSet::from([Tag::Misc, Tag::Container]),
+            }]
+        );
+        assert_eq!(
+            world.objects_with_tag(&Tag::Container).collect::<Vec<_>>(),
+            vec![0xA]
+        );
+
+        let events = world.apply(Record::from_str("A,Type=Container+Misc").unwrap());
+        assert!(events.is_empty());
+
+        let events = world.apply(Record::from_str("A,Type=Misc+Shrapnel").unwrap());
+        assert_eq!(
+            events,
+            vec![WorldEvent::TypeChanged {
+                id: 0xA,
+                old: HashSet::from([Tag::Misc, Tag::Container]),
+                new: HashSet::from([Tag::Misc, Tag::Shrapnel]),
+            }]
+        );
+        assert_eq!(world.objects_with_tag(&Tag::Container).count(), 0);
+        assert_eq!(
+            world.objects_with_tag(&Tag::Shrapnel).collect::<Vec<_>>(),
+            vec![0xA]
+        );
+
+        world.apply(Record::Remove(0xA));
+        assert_eq!(world.objects_with_tag(&Tag::Misc).count(), 0);
+    }
+
+    #[test]
+    fn test_removals() {
+        let mut world = World::new();
+        world.apply(Record::from_str("A,Name=F-16C-52").unwrap());
+        world.apply(Record::from_str("B,Name=F-16C-52").unwrap());
+        world.apply(Record::from_str("-A").unwrap());
+        world.apply(Record::from_str("0,Event=Destroyed|B|").unwrap());
+        assert!(world.object(0xA).is_none());
+        assert!(world.object(0xB).is_some());
+
+        let mut world = World::new().with_event_removals(true);
+        world.apply(Record::from_str("A,Name=F-16C-52").unwrap());
+        world.apply(Record::from_str("B,Name=F-16C-52").unwrap());
+        world.apply(Record::from_str("0,Event=Destroyed|A|").unwrap());
+        world.apply(Record::from_str("0,Event=LeftArea|B|").unwrap());
+        assert_eq!(world.objects().count(), 0);
+    }
+
+    #[test]
+    fn test_header_and_mission_changed() {
+        let header = Header {
+            file_type: "text/acmi/tacview".to_string(),
+            file_version: "2.2".to_string(),
+        };
+        let mut world = World::new();
+        assert!(world.set_header(header.clone()).is_empty());
+        world.apply(Record::from_str("0,RecordingTime=2016-02-18T16:44:12Z,Title=A").unwrap());
+        world.apply(Record::from_str("A,Name=F-16C-52").unwrap());
+
+        // reconnected to the same mission
+        assert!(world.set_header(header.clone()).is_empty());
+        let events = world.apply(Record::from_str("0,RecordingTime=2016-02-18T16:44:12Z").unwrap());
+        assert!(events.is_empty());
+        assert!(world.object(0xA).is_some());
+
+        // reconnected to another mission
+        let header = Header {
+            file_version: "2.2.1".to_string(),
+            ..header
+        };
+        assert_eq!(
+            world.set_header(header.clone()),
+            vec![WorldEvent::HeaderChanged(header.clone())]
+        );
+        let events = world.apply(Record::from_str("0,RecordingTime=2016-02-18T18:00:00Z").unwrap());
+        assert_eq!(events, vec![WorldEvent::MissionChanged]);
+        assert_eq!(world.objects().count(), 0);
+        assert_eq!(world.global_properties().count(), 1);
+        assert_eq!(world.header(), Some(&header));
+    }
+
+    #[test]
+    fn test_duplicate_property() {
+        let line = "A,Name=F-16C-52,HDG=10,Name=F-15C";
+        let mut world = World::new();
+        assert!(world.apply(Record::from_str(line).unwrap()).is_empty());
+        assert_eq!(world.object(0xA).unwrap().name(), Some("F-15C"));
+
+        let mut world = World::new().with_duplicate_reports(true);
+        let events = world.apply(Record::from_str(line).unwrap());
+        assert_eq!(
+            events,
+            vec![WorldEvent::DuplicateProperty {
+                id: 0xA,
+                property: ObjectProperty::Name("F-15C".into()),
+            }]
+        );
+        assert_eq!(world.object(0xA).unwrap().name(), Some("F-15C"));
+
+        assert!(matches!(
+            Record::from_str_rejecting_duplicates(line),
+            Err(Error::DuplicateProperty(_))
+        ));
+    }
+
+    #[test]
+    fn test_visibility() {
+        let mut world = World::new();
+        world.apply(Record::from_str("A,Name=F-16C-52").unwrap());
+        world.apply(Record::from_str("B,Visible=0").unwrap());
+        assert!(world.is_visible(0xA));
+        assert!(!world.is_visible(0xB));
+        assert!(!world.is_visible(0xC));
+        assert_eq!(
+            world
+                .visible_objects()
+                .map(|(id, _)| id)
+                .collect::<Vec<_>>(),
+            vec![0xA]
+        );
+
+        world.apply(Record::from_str("B,Visible=0.3").unwrap());
+        assert!(world.is_visible(0xB));
+
+        let mut world = world.with_visibility_threshold(0.5);
+        assert!(!world.is_visible(0xB));
+        world.apply(Record::from_str("B,Visible=1").unwrap());
+        assert_eq!(world.visible_objects().count(), 2);
+    }
+
+    #[test]
+    fn test_health_depleted() {
+        let mut world = World::new();
+        assert!(world
+            .apply(Record::from_str("A,Health=1").unwrap())
+            .is_empty());
+        assert!(!world.object(0xA).unwrap().is_destroyed());
+
+        let events = world.apply(Record::from_str("A,Health=0").unwrap());
+        assert_eq!(events, vec![WorldEvent::HealthDepleted { id: 0xA }]);
+        assert!(world.object(0xA).unwrap().is_destroyed());
+
+        for line in ["A,Health=0", "A,Health=0.5", "A,Health=0"] {
+            assert!(world.apply(Record::from_str(line).unwrap()).is_empty());
+        }
+
+        // a new object with the same id
+        world.apply(Record::Remove(0xA));
+        let events = world.apply(Record::from_str("A,Health=0").unwrap());
+        assert_eq!(events, vec![WorldEvent::HealthDepleted { id: 0xA }]);
+    }
+
+    #[test]
+    fn test_bullseye() {
+        let mut world = World::new();
+        assert!(world.bullseye(None).is_none());
+        world.apply(
+            Record::from_str("B,T=1|2|0,Type=Navaid+Static+Bullseye,Coalition=Enemies").unwrap(),
+        );
+        world.apply(
+            Record::from_str("A,T=3|4|0,Type=Navaid+Static+Bullseye,Coalition=Allies").unwrap(),
+        );
+        world.apply(Record::from_str("C,T=3|4|0,Type=Air+FixedWing,Coalition=Allies").unwrap());
+
+        assert_eq!(world.bullseye(None), world.object(0xA));
+        assert_eq!(world.bullseye(Some("Enemies")), world.object(0xB));
+        assert!(world.bullseye(Some("Neutrals")).is_none());
+    }
+
+    #[test]
+    fn test_deduplication() {
+        let mut world = World::new().with_deduplication(1e-6);
+        world.apply(Record::from_str("A,T=1|2|3,Name=F-16C-52,HDG=90").unwrap());
+        world.apply(Record::from_str("#1").unwrap());
+
+        let events =
+            world.apply(Record::from_str("A,T=1|2|3,Name=F-16C-52,HDG=90.0000001").unwrap());
+        assert!(events.is_empty());
+        assert_eq!(world.frame_updates().count(), 0);
+
+        let events = world.apply(Record::from_str("A,T=1|2.5|3,Name=F-16C-52,HDG=91").unwrap());
+        assert_eq!(
+            events,
+            vec![WorldEvent::PropertiesChanged {
+                id: 0xA,
+                properties: vec![
+                    ObjectProperty::T(Coords {
+                        latitude: Some(2.5),
+                        ..Default::default()
+                    }),
+                    ObjectProperty::Hdg(91.0),
+                ],
+            }]
+        );
+        assert_eq!(world.frame_updates().count(), 1);
+        let object = world.object(0xA).unwrap();
+        assert_eq!(object.coords().unwrap().longitude, Some(1.0));
+        assert_eq!(object.coords().unwrap().latitude, Some(2.5));
+    }
+
+    #[test]
+    fn test_counts() {
+        let mut world = World::new();
+        let empty = world.estimated_bytes();
+        assert_eq!(world.object_count(), 0);
+
+        for id in 1..=100 {
+            world.apply(Record::from_str(&format!("{id:X},T=1|2|3,Type=Air")).unwrap());
+        }
+        assert_eq!(world.object_count(), 100);
+        assert!(world.estimated_bytes() > empty + 100 * std::mem::size_of::<ObjectState>());
+
+        for id in 1..=60 {
+            world.apply(Record::Remove(id));
+        }
+        assert_eq!(world.object_count(), 40);
+    }
+
+    #[test]
+    fn test_late_reference() {
+        for reoffset in [false, true] {
+            let mut world = World::new().with_late_reference_reoffset(reoffset);
+            world.apply(Record::from_str("A,T=1|2|3").unwrap());
+            assert!(!world.is_reference_adjusted(0xA));
+            assert_eq!(world.absolute_coords(0xA).unwrap().longitude, Some(1.0));
+
+            world
+                .apply(Record::from_str("0,ReferenceLongitude=-129,ReferenceLatitude=43").unwrap());
+            world.apply(Record::from_str("B,T=1|2|3").unwrap());
+            assert!(world.is_reference_adjusted(0xB));
+            assert_eq!(world.absolute_coords(0xB).unwrap().longitude, Some(-128.0));
+            assert_eq!(world.absolute_coords(0xB).unwrap().latitude, Some(45.0));
+
+            assert_eq!(world.is_reference_adjusted(0xA), reoffset);
+            let expected = if reoffset { -128.0 } else { 1.0 };
+            assert_eq!(
+                world.absolute_coords(0xA).unwrap().longitude,
+                Some(expected)
+            );
+
+            // positioned again, after the reference
+            world.apply(Record::from_str("A,T=2|2|3").unwrap());
+            assert!(world.is_reference_adjusted(0xA));
+            assert_eq!(world.absolute_coords(0xA).unwrap().longitude, Some(-127.0));
+        }
+    }
+
+    #[test]
+    fn test_frame_spawns() {
+        let mut world = World::new();
+        world.apply(Record::from_str("#0").unwrap());
+        world.apply(Record::from_str("A,T=1|2|3").unwrap());
+        world.apply(Record::from_str("B,T=1|2|3").unwrap());
+        world.apply(Record::from_str("-B").unwrap());
+        assert_eq!(
+            world.frame_spawns().map(|(id, _)| id).collect::<Vec<_>>(),
+            vec![0xA]
+        );
+
+        world.apply(Record::from_str("#1").unwrap());
+        world.apply(Record::from_str("A,T=1|2|4").unwrap());
+        assert_eq!(world.frame_spawns().count(), 0);
+
+        world.apply(Record::from_str("#2").unwrap());
+        world.apply(Record::from_str("B,T=1|2|3").unwrap());
+        assert_eq!(
+            world.frame_spawns().map(|(id, _)| id).collect::<Vec<_>>(),
+            vec![0xB]
+        );
+    }
+
+    #[test]
+    fn test_track_history() {
+        let mut world = World::new().with_track_history(3);
+        for line in [
+            "#0",
+            "A,T=1|2|0,Type=Misc+Explosion,Radius=10",
+            "#1",
+            "A,Radius=20",
+            "A,Radius=25",
+            "#2",
+            "A,Name=Explosion",
+            "#3",
+            "A,Radius=40",
+            "#4",
+            "A,T=||10,Radius=80",
+        ] {
+            world.apply(Record::from_str(line).unwrap());
+        }
+
+        assert_eq!(world.object(0xA).unwrap().radius_m(), Some(80.0));
+        let samples: Vec<_> = world
+            .track(0xA)
+            .map(|sample| (sample.time, sample.radius))
+            .collect();
+        assert_eq!(
+            samples,
+            vec![(1.0, Some(25.0)), (3.0, Some(40.0)), (4.0, Some(80.0))]
+        );
+        assert_eq!(world.track(0xA).last().unwrap().coords.altitude, Some(10.0));
+        assert_eq!(world.track_sample_count(), 3);
+
+        world.apply(Record::Remove(0xA));
+        assert_eq!(world.track_sample_count(), 0);
+    }
+
+    #[test]
+    fn test_validation() {
+        let mut world = World::new().with_validation(true);
+        world.apply(Record::from_str("A,T=1|2|3").unwrap());
+        world.apply(Record::from_str("0,Event=Destroyed|A|").unwrap());
+        assert!(world.take_warnings().is_empty());
+
+        world.apply(Record::from_str("B,T=1|200|3").unwrap());
+        world.apply(Record::from_str("0,Event=Destroyed|C|").unwrap());
+        let warnings = world.take_warnings();
+        assert_eq!(warnings.len(), 2);
+        assert!(matches!(
+            warnings[0],
+            Warning::CoordsOutOfRange { id: 0xB, .. }
+        ));
+        assert_eq!(warnings[1], Warning::UnknownObject { id: 0xC });
+        assert!(world.take_warnings().is_empty());
+    }
+
+    #[test]
+    fn test_name_resolver() {
+        let mut world = World::new();
+        world.set_name_resolver(|name, tags| {
+            (name == "F-16C-52" && tags.contains(&Tag::FixedWing)).then(|| Metadata {
+                full_name: Some("General Dynamics F-16C Fighting Falcon".to_string()),
+                role: Some("Multirole fighter".to_string()),
+                image: None,
+            })
+        });
+
+        world.apply(Record::from_str("A,T=1|2|3,Name=F-16C-52").unwrap());
+        assert_eq!(world.objects[&0xA].metadata(), None);
+        world.apply(Record::from_str("A,Type=Air+FixedWing").unwrap());
+        assert_eq!(
+            world.objects[&0xA].metadata().unwrap().role.as_deref(),
+            Some("Multirole fighter")
+        );
+
+        world.apply(Record::from_str("B,T=1|2|3,Name=Unknown,Type=Air+FixedWing").unwrap());
+        assert_eq!(world.objects[&0xB].metadata(), None);
+    }
+
+    #[test]
+    fn test_write_snapshot() {
+        let mut world = World::new();
+        for line in [
+            "0,ReferenceLongitude=-129,Title=Test",
+            "#0",
+            "A,T=1|2|3,Name=F-16C-52,Type=Air+FixedWing",
+            "B,T=4|5|6,Coalition=Allies",
+            "#12.5",
+            "A,T=||300,IAS=150.5",
+            "-B",
+            "C,T=7|8|9|10|11|12,Label=x=y",
+        ] {
+            world.apply(Record::from_str(line).unwrap());
+        }
+
+        let mut buf = Vec::new();
+        world.write_snapshot(&mut buf).unwrap();
+        let reader = crate::acmi::file::FileReader::try_from_reader(&buf[..]).unwrap();
+        let mut reparsed = World::new();
+        for record in reader {
+            reparsed.apply(record.unwrap());
+        }
+
+        assert_eq!(reparsed.time(), 12.5);
+        assert_eq!(reparsed.object_count(), 2);
+        assert_eq!(reparsed.snapshot(), world.snapshot());
+        assert!(String::from_utf8(buf)
+            .unwrap()
+            .starts_with("FileType=text/acmi/tacview\nFileVersion=2.2\n"));
+    }
+
+    #[test]
+    fn test_retain_destroyed() {
+        let records = [
+            "#0",
+            "A,T=1|2|3,Name=F-16C-52",
+            "B,T=4|5|6",
+            "#1",
+            "A,T=1.1|2|3",
+            "#2",
+            "-A",
+            "0,Event=Destroyed|B|",
+        ];
+
+        let mut world = World::new()
+            .with_event_removals(true)
+            .with_track_history(10)
+            .with_retain_destroyed(true);
+        for record in records {
+            world.apply(Record::from_str(record).unwrap());
+        }
+        assert_eq!(world.object_count(), 0);
+        let mut destroyed: Vec<_> = world.destroyed().collect();
+        destroyed.sort_unstable_by_key(|(id, _)| *id);
+        assert_eq!(destroyed.len(), 2);
+        let (id, a) = destroyed[0];
+        assert_eq!(id, 0xA);
+        assert_eq!(a.time, 2.0);
+        assert_eq!(a.state.name(), Some("F-16C-52"));
+        assert_eq!(a.track.len(), 2);
+        assert_eq!(a.track[1].coords.longitude, Some(1.1));
+
+        let mut world = World::new()
+            .with_event_removals(true)
+            .with_track_history(10);
+        for record in records {
+            world.apply(Record::from_str(record).unwrap());
+        }
+        assert_eq!(world.destroyed().count(), 0);
+        assert_eq!(world.track_sample_count(), 0);
+    }
+
+    #[test]
+    fn test_unknown_properties() {
+        let mut world = World::new();
+        world.apply(Record::from_str("0,Foo=1,Bar=2").unwrap());
+        world.apply(Record::from_str("A,Foo=1,Bar=2").unwrap());
+        world.apply(Record::from_str("A,Foo=3").unwrap());
+
+        let object = world.object(0xA).unwrap();
+        assert_eq!(
+            object.unknown("Foo").and_then(UnknownValue::as_u64),
+            Some(3)
+        );
+        assert_eq!(
+            object.unknown("Bar").and_then(UnknownValue::as_u64),
+            Some(2)
+        );
+        assert_eq!(object.properties().count(), 2);
+
+        let mut global_properties: Vec<_> =
+            world.global_properties().map(ToString::to_string).collect();
+        global_properties.sort();
+        assert_eq!(global_properties, ["Bar=2", "Foo=1"]);
+        assert!(world
+            .snapshot()
+            .contains(&Record::from_str("A,Bar=2,Foo=3").unwrap()));
+    }
+
+    #[test]
+    fn test_group_members() {
+        let mut world = World::new();
+        for line in [
+            "1,Group=Viper 1,Name=lead",
+            "2,Group=Viper 1,Slot=3,Name=four",
+            "3,Group=Viper 1,Slot=1,Name=two",
+            "4,Group=Viper 2,Slot=0,Name=other",
+            "5,Group=Viper 1,Slot=2,Name=three",
+        ] {
+            world.apply(Record::from_str(line).unwrap());
+        }
+
+        let names: Vec<_> = world
+            .group_members("Viper 1")
+            .into_iter()
+            .filter_map(ObjectState::name)
+            .collect();
+        assert_eq!(names, ["two", "three", "four", "lead"]);
+        assert_eq!(world.group_leader("Viper 1").unwrap().name(), Some("two"));
+
+        world.apply(Record::from_str("1,Slot=0").unwrap());
+        assert_eq!(world.group_leader("Viper 1").unwrap().name(), Some("lead"));
+        assert_eq!(world.group_leader("Viper 3"), None);
+    }
+
+    #[test]
+    fn test_frame_rate() {
+        let mut world = World::new();
+        assert_eq!(world.frame_rate_hz(), None);
+        world.apply(Record::Frame(0.0));
+        world.apply(Record::Frame(0.0));
+        assert_eq!(world.frame_rate_hz(), None);
+        for frame in 1..=10 {
+            world.apply(Record::Frame(f64::from(frame) * 0.2));
+        }
+        assert!((world.frame_rate_hz().unwrap() - 5.0).abs() < 1e-9);
+
+        // a pause only weighs on the window until it slides past it
+        world.apply(Record::Frame(10.0));
+        assert!(world.frame_rate_hz().unwrap() < 2.0);
+        for frame in 1..=FRAME_RATE_WINDOW {
+            world.apply(Record::Frame(10.0 + frame as f64 * 0.1));
+        }
+        assert!((world.frame_rate_hz().unwrap() - 10.0).abs() < 1e-9);
+
+        world.apply(Record::Frame(0.0));
+        assert_eq!(world.frame_rate_hz(), None);
+    }
+
+    #[test]
+    fn test_time_reset() {
+        let mut world = World::new();
+        world.apply(Record::from_str("0,ReferenceTime=2011-06-02T05:00:00Z").unwrap());
+        assert!(world.apply(Record::Frame(-2.0)).is_empty());
+        assert!(world.apply(Record::Frame(10.0)).is_empty());
+        world.apply(Record::from_str("A,T=1|2|3").unwrap());
+        assert_eq!(
+            world.apply(Record::Frame(0.0)),
+            vec![WorldEvent::TimeReset {
+                previous: 10.0,
+                time: 0.0,
+            }]
+        );
+        assert_eq!(world.object_count(), 1);
+        assert_eq!(
+            world.absolute_time().unwrap().unix_timestamp(),
+            1_306_990_800
+        );
+        assert!(world.apply(Record::Frame(0.5)).is_empty());
+    }
+
+    #[test]
+    fn test_absolute_time_overflow() {
+        let mut world = World::new();
+        world.apply(Record::from_str("0,ReferenceTime=2011-06-02T05:00:00Z").unwrap());
+        world.apply(Record::from_str("#1e300").unwrap());
+        assert_eq!(world.absolute_time(), None);
+        world.apply(Record::from_str("#1e12").unwrap());
+        assert_eq!(world.absolute_time(), None);
+        world.apply(Record::from_str("#-1e300").unwrap());
+        assert_eq!(world.absolute_time(), None);
+        world.apply(Record::from_str("#1").unwrap());
+        assert_eq!(
+            world.absolute_time().unwrap().unix_timestamp(),
+            1_306_990_801
+        );
+    }
+
+    #[test]
+    fn test_reidentification() {
+        let apply = |world: &mut World, lines: &[&str]| {
+            lines
+                .iter()
+                .flat_map(|line| world.apply(Record::from_str(line).unwrap()))
+                .filter(|event| matches!(event, WorldEvent::Reidentified { .. }))
+                .collect::<Vec<_>>()
+        };
+        let mut world = World::new().with_reidentification(100.0, 2.0);
+        apply(
+            &mut world,
+            &["0,ReferenceLongitude=-129,ReferenceLatitude=43", "#0"],
+        );
+        apply(&mut world, &["A,T=0.1|0.1|1000,Name=Fuel Tank"]);
+        apply(&mut world, &["B,T=0.2|0.1|1000,Name=Fuel Tank"]);
+
+        // same name, close, and soon enough
+        let events = apply(
+            &mut world,
+            &["#1", "-A", "#2", "C,T=0.1|0.1001|900,Name=Fuel Tank"],
+        );
+        assert_eq!(
+            events,
+            vec![WorldEvent::Reidentified { old: 0xA, new: 0xC }]
+        );
+
+        // too far
+        let events = apply(&mut world, &["-B", "D,T=0.3|0.1|1000,Name=Fuel Tank"]);
+        assert!(events.is_empty());
+
+        // too late
+        let events = apply(
+            &mut world,
+            &["-C", "#5", "E,T=0.1|0.1001|900,Name=Fuel Tank"],
+        );
+        assert!(events.is_empty());
+
+        // another name
+        let events = apply(&mut world, &["-D", "F,T=0.3|0.1|1000,Name=Mk-82"]);
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_bra_label() {
+        let mut world = World::new();
+        for line in [
+            "0,ReferenceLongitude=-129,ReferenceLatitude=43",
+            "#0",
+            "B,T=0|0|0,Type=Navaid+Static+Bullseye,Coalition=Allies",
+            "A,T=-1|0|4572",
+            "C,T=0|-0.1|",
+        ] {
+            world.apply(Record::from_str(line).unwrap());
+        }
+        // 1 degree of longitude at 43 degrees of latitude is ~44 nm
+        assert_eq!(
+            world.bra_label(0xA, Some("Allies")).as_deref(),
+            Some("BRA 270/44/15000")
+        );
+        assert_eq!(world.bra_label(0xA, Some("Enemies")), None);
+        assert_eq!(world.bra_label(0xC, None), None);
+        assert_eq!(world.bra_label(0xD, None), None);
+    }
+}
@@ -0,0 +1,211 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
+
+use crate::error::{Error, Result};
+
+use super::{record::Record, world::World, Header};
+
+/// Writes records to plaintext ACMI files, starting a new file whenever the
+/// frame time crosses the chunk duration.
+///
+/// Chunks are written to `{base_path}-{index}.txt.acmi`, with a zero-padded
+/// index starting at 0. Each new chunk starts with the header, the global
+/// properties and the full state of every object, so it can be replayed on
+/// its own.
+#[derive(Debug)]
+pub struct RotatingAcmiWriter {
+    base_path: PathBuf,
+    header: Header,
+    chunk_duration: f64,
+    world: World,
+    /// Index of the next chunk, only advanced once its file is written.
+    next_index: usize,
+    /// First frame time and file of the current chunk.
+    chunk: Option<(Option<f64>, BufWriter<File>)>,
+}
+
+impl RotatingAcmiWriter {
+    /// `chunk_duration` is in seconds of frame time.
+    pub fn new(base_path: impl Into<PathBuf>, header: Header, chunk_duration: f64) -> Self {
+        Self {
+            base_path: base_path.into(),
+            header,
+            chunk_duration,
+            world: World::new(),
+            next_index: 0,
+            chunk: None,
+        }
+    }
+
+    /// If starting a new chunk fails, the current one is kept and rotation is
+    /// retried on the next frame.
+    pub fn write(&mut self, record: &Record) -> Result<()> {
+        let rotate = match (&mut self.chunk, record) {
+            (None, _) => true,
+            (Some((Some(start), _)), Record::Frame(time)) => time - *start >= self.chunk_duration,
+            (Some((start @ None, _)), Record::Frame(time)) => {
+                *start = Some(*time);
+                false
+            }
+            _ => false,
+        };
+        self.world.apply(record.clone());
+
+        if rotate {
+            if let Some((_, writer)) = &mut self.chunk {
+                writer.flush().map_err(Error::AcmiWriterWrite)?;
+            }
+            let index = self.next_index;
+            let path = format!("{}-{index:04}.txt.acmi", self.base_path.display());
+            let file = File::create(path).map_err(Error::AcmiWriterWrite)?;
+            let mut writer = BufWriter::new(file);
+            self.write_header(&mut writer)
+                .map_err(Error::AcmiWriterWrite)?;
+            if index > 0 {
+                // the snapshot starts with the current frame, which is the record
+                self.write_snapshot(&mut writer)
+                    .map_err(Error::AcmiWriterWrite)?;
+                self.next_index += 1;
+                self.chunk = Some((Some(self.world.time()), writer));
+                return Ok(());
+            }
+            let start = matches!(record, Record::Frame(_)).then(|| self.world.time());
+            self.next_index += 1;
+            self.chunk = Some((start, writer));
+        }
+
+        let (_, writer) = self.chunk.as_mut().unwrap();
+        record.write_acmi(writer).map_err(Error::AcmiWriterWrite)
+    }
+
+    /// Flushes the current chunk.
+    pub fn flush(&mut self) -> Result<()> {
+        match &mut self.chunk {
+            Some((_, writer)) => writer.flush().map_err(Error::AcmiWriterWrite),
+            None => Ok(()),
+        }
+    }
+
+    fn write_header(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        writeln!(writer, "FileType={}", self.header.file_type)?;
+        writeln!(writer, "FileVersion={}", self.header.file_version)
+    }
+
+    fn write_snapshot(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        for record in self.world.snapshot() {
+            record.write_acmi(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for RotatingAcmiWriter {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::{acmi::file::FileReader, temp_dir::TempDir};
+
+    #[test]
+    fn test_rotation() {
+        let dir = TempDir::new("test-rotation");
+        let base_path = dir.path().join("mission");
+        let header = Header {
+            file_type: "text/acmi/tacview".to_string(),
+            file_version: "2.2".to_string(),
+        };
+        let mut writer = RotatingAcmiWriter::new(&base_path, header, 10.0);
+        for line in [
+            "0,ReferenceLongitude=-129",
+            "#0",
+            "A,T=1|2|3,Name=F-16C-52",
+            "#5",
+            "B,T=4|5|6",
+            "#12",
+            "A,T=||300",
+            "#21",
+            "#25",
+            "-B",
+        ] {
+            writer.write(&Record::from_str(line).unwrap()).unwrap();
+        }
+        drop(writer);
+
+        let mut worlds = Vec::new();
+        for index in 0..3 {
+            let path = format!("{}-{index:04}.txt.acmi", base_path.display());
+            let mut world = World::new();
+            let file = std::io::BufReader::new(std::fs::File::open(&path).unwrap());
+            for record in FileReader::try_from_reader(file).unwrap() {
+                world.apply(record.unwrap());
+            }
+            worlds.push(world);
+        }
+
+        assert_eq!(worlds[0].time(), 5.0);
+        assert_eq!(worlds[0].objects().count(), 2);
+        assert_eq!(worlds[1].time(), 21.0);
+        assert_eq!(worlds[1].objects().count(), 2);
+        let a = worlds[1].object(0xA).unwrap();
+        assert_eq!(a.name(), Some("F-16C-52"));
+        assert_eq!(a.coords().unwrap().altitude, Some(300.0));
+        assert_eq!(worlds[2].time(), 25.0);
+        assert_eq!(worlds[2].objects().count(), 1);
+        assert_eq!(worlds[2].global_properties().count(), 1);
+    }
+
+    #[test]
+    fn test_rotation_failure() {
+        let dir = TempDir::new("test-rotation-failure");
+        let base_path = dir.path().join("mission");
+        let header = Header {
+            file_type: "text/acmi/tacview".to_string(),
+            file_version: "2.2".to_string(),
+        };
+        // a directory in the way of the second chunk
+        let blocked = format!("{}-0001.txt.acmi", base_path.display());
+        std::fs::create_dir(&blocked).unwrap();
+
+        let mut writer = RotatingAcmiWriter::new(&base_path, header, 10.0);
+        for line in ["#0", "A,T=1|2|3,Name=F-16C-52", "#5"] {
+            writer.write(&Record::from_str(line).unwrap()).unwrap();
+        }
+        assert!(matches!(
+            writer.write(&Record::from_str("#12").unwrap()),
+            Err(Error::AcmiWriterWrite(_))
+        ));
+        writer
+            .write(&Record::from_str("B,T=4|5|6").unwrap())
+            .unwrap();
+        std::fs::remove_dir(&blocked).unwrap();
+        writer.write(&Record::from_str("#15").unwrap()).unwrap();
+        drop(writer);
+
+        let read = |index: usize| {
+            let path = format!("{}-{index:04}.txt.acmi", base_path.display());
+            let mut world = World::new();
+            let file = std::io::BufReader::new(std::fs::File::open(&path).unwrap());
+            for record in FileReader::try_from_reader(file).unwrap() {
+                world.apply(record.unwrap());
+            }
+            world
+        };
+        // the first chunk is kept, with the records written after the failure
+        let first = read(0);
+        assert_eq!(first.time(), 5.0);
+        assert_eq!(first.objects().count(), 2);
+        let second = read(1);
+        assert_eq!(second.time(), 15.0);
+        assert_eq!(second.objects().count(), 2);
+        assert!(!std::path::Path::new(&format!("{}-0002.txt.acmi", base_path.display())).exists());
+    }
+}
@@ -0,0 +1,198 @@
+use tokio::{
+    io::{AsyncBufRead, BufStream},
+    net::{TcpStream, ToSocketAddrs},
+};
+
+use crate::{
+    acmi::{
+        record::Record,
+        state::ObjectState,
+        world::{World, WorldEvent},
+        Header, RealTimeReader,
+    },
+    error::Result,
+};
+
+/// Real-time telemetry client keeping the state of the mission: the
+/// recommended starting point.
+///
+/// It bundles a [`RealTimeReader`] with a [`World`] applying every record.
+/// Call [`poll`](Self::poll) in a loop, then read the state through the
+/// accessors or [`world`](Self::world). Use [`RealTimeReader`] directly to
+/// handle the records yourself.
+#[derive(Debug)]
+pub struct TacviewClient<R = BufStream<TcpStream>> {
+    reader: RealTimeReader<R>,
+    world: World,
+    /// Frame read by the last poll, starting the next one.
+    pending: Option<Record>,
+    /// Events of the frame a failed poll was reading, returned by the next
+    /// one.
+    partial: Option<Vec<WorldEvent>>,
+}
+
+impl TacviewClient {
+    /// Connects with [`connect`](crate::connect).
+    pub async fn connect<A>(addr: A, username: &str, password: &str) -> Result<Self>
+    where
+        A: ToSocketAddrs,
+    {
+        let reader = crate::connect(addr, username, password).await?;
+        Ok(Self::new(reader))
+    }
+}
+
+impl<R> TacviewClient<R>
+where
+    R: AsyncBufRead + Unpin,
+{
+    pub fn new(reader: RealTimeReader<R>) -> Self {
+        Self::with_world(reader, World::new())
+    }
+
+    /// Same as [`new`](Self::new), with a [`World`] configured with its
+    /// options.
+    pub fn with_world(reader: RealTimeReader<R>, mut world: World) -> Self {
+        world.set_header(reader.header.clone());
+        Self {
+            reader,
+            world,
+            pending: None,
+            partial: None,
+        }
+    }
+
+    /// Reads and applies the records of the next frame: the frame itself and
+    /// every record up to the following one. Returns the events they caused,
+    /// empty once the stream has [ended](Self::is_closed).
+    ///
+    /// On error, the records already applied stay applied and their events
+    /// are returned by the next poll, which carries on with the same frame.
+    pub async fn poll(&mut self) -> Result<Vec<WorldEvent>> {
+        let mut applied = self.partial.is_some();
+        let mut events = self.partial.take().unwrap_or_default();
+        if let Some(record) = self.pending.take() {
+            events.extend(self.world.apply(record));
+            applied = true;
+        }
+        loop {
+            let record = match self.reader.next().await {
+                Ok(Some(record)) => record,
+                Ok(None) => break,
+                Err(error) => {
+                    if applied {
+                        self.partial = Some(events);
+                    }
+                    return Err(error);
+                }
+            };
+            if applied && matches!(record, Record::Frame(_)) {
+                self.pending = Some(record);
+                break;
+            }
+            events.extend(self.world.apply(record));
+            applied = true;
+        }
+        Ok(events)
+    }
+
+    /// Whether the stream has ended and every record has been applied.
+    pub fn is_closed(&self) -> bool {
+        self.pending.is_none() && self.partial.is_none() && self.reader.is_closed()
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.reader.header
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    /// Time offset of the current frame, in seconds.
+    pub fn current_time(&self) -> f64 {
+        self.world.time()
+    }
+
+    pub fn objects(&self) -> impl Iterator<Item = (u64, &ObjectState)> {
+        self.world.objects()
+    }
+
+    pub fn object(&self, id: u64) -> Option<&ObjectState> {
+        self.world.object(id)
+    }
+
+    /// See [`World::bullseye`].
+    pub fn bullseye(&self, coalition: Option<&str>) -> Option<&ObjectState> {
+        self.world.bullseye(coalition)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_poll() {
+        use super::*;
+        use crate::testing::MockServer;
+
+        let script = [
+            "#0",
+            "A,T=1|2|3,Name=F-16C-52",
+            "B,T=4|5|6",
+            "#1",
+            "-B",
+            "#2",
+        ];
+        let server = MockServer::bind(script).await.unwrap();
+        let addr = server.local_addr().unwrap();
+        let (login, client) = tokio::join!(server.serve_one(), async {
+            let mut client = TacviewClient::connect(addr, "pilot", "").await.unwrap();
+
+            client.poll().await.unwrap();
+            assert_eq!(client.current_time(), 0.0);
+            assert_eq!(client.objects().count(), 2);
+            assert_eq!(client.object(0xA).unwrap().name(), Some("F-16C-52"));
+
+            client.poll().await.unwrap();
+            assert_eq!(client.current_time(), 1.0);
+            assert_eq!(client.objects().count(), 1);
+
+            client.poll().await.unwrap();
+            assert_eq!(client.current_time(), 2.0);
+            client
+        });
+        login.unwrap();
+        let mut client = client;
+        assert!(client.is_closed());
+        assert!(client.poll().await.unwrap().is_empty());
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn test_poll_error() {
+        use super::*;
+
+        const ACMI: &[u8] = b"FileType=text/acmi/tacview\nFileVersion=2.2\n\
+            #0\nA,T=1|2|3,Type=Air+FixedWing\nB,HDG=x\nC,T=4|5|6\n#1\n";
+        let reader = RealTimeReader::try_from_reader(ACMI).await.unwrap();
+        let mut client = TacviewClient::new(reader);
+
+        assert!(client.poll().await.is_err());
+        assert_eq!(client.objects().count(), 1);
+        assert!(!client.is_closed());
+
+        // the events of the records applied before the error are not lost
+        let events = client.poll().await.unwrap();
+        assert!(matches!(
+            events.as_slice(),
+            [WorldEvent::TypeChanged { id: 0xA, .. }]
+        ));
+        assert_eq!(client.current_time(), 0.0);
+        assert_eq!(client.objects().count(), 2);
+
+        assert!(client.poll().await.unwrap().is_empty());
+        assert_eq!(client.current_time(), 1.0);
+        assert!(client.is_closed());
+    }
+}
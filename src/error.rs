@@ -16,10 +16,15 @@ pub enum Error {
     TcpEndOfHeader(u8),
     #[error("failed to read from ACMI reader: {0}")]
     AcmiReaderRead(#[source] std::io::Error),
+    #[error("failed to write ACMI file: {0}")]
+    AcmiWriterWrite(#[source] std::io::Error),
     #[error("bad ACMI file type header, found: {0}")]
     BadAcmiFileType(String),
     #[error("bad ACMI file version header, found: {0}")]
     BadAcmiFileVersion(String),
+    #[cfg(feature = "file")]
+    #[error("failed to read zipped ACMI file: {0}")]
+    Zip(#[source] zip::result::ZipError),
     #[error("unexpected end-of-line from ACMI reader")]
     AcmiReaderEol,
     #[error("failed to parse integer: {0}")]
@@ -28,6 +33,8 @@ pub enum Error {
     ParseDateTime(#[source] time::error::Parse),
     #[error("failed to parse float: {0}")]
     ParseFloat(#[source] ParseFloatError),
+    #[error("malformed frame, found: {0}")]
+    MalformedFrame(String),
     #[error("malformed event, found: {0}")]
     MalformedEvent(String),
     #[error("malformed global property, found: {0}")]
@@ -36,6 +43,140 @@ pub enum Error {
     MalformedObjectProperty(String),
     #[error("malformed coordinates, found: {0}")]
     MalformedCoords(String),
+    #[error("unexpected ACMI line, found: {0}")]
+    UnexpectedLine(String),
+    #[error("duplicate object property, found: {0}")]
+    DuplicateProperty(String),
+}
+
+impl Error {
+    /// Failed to read from or write to the underlying stream.
+    pub fn is_io(&self) -> bool {
+        match self {
+            Self::TcpConnect(_)
+            | Self::TcpRead(_)
+            | Self::TcpWrite(_)
+            | Self::AcmiReaderRead(_)
+            | Self::AcmiWriterWrite(_) => true,
+            #[cfg(feature = "file")]
+            Self::Zip(zip::result::ZipError::Io(_)) => true,
+            _ => false,
+        }
+    }
+
+    /// The stream is readable but a record in it is malformed.
+    pub fn is_parse(&self) -> bool {
+        let is_parse = match self {
+            #[cfg(feature = "file")]
+            Self::Zip(_) => true,
+            Self::AcmiReaderEol
+            | Self::ParseInt(_)
+            | Self::ParseDateTime(_)
+            | Self::ParseFloat(_)
+            | Self::MalformedFrame(_)
+            | Self::MalformedEvent(_)
+            | Self::MalformedGlobalProperty(_)
+            | Self::MalformedObjectProperty(_)
+            | Self::MalformedCoords(_)
+            | Self::UnexpectedLine(_)
+            | Self::DuplicateProperty(_) => true,
+            _ => false,
+        };
+        is_parse && !self.is_io()
+    }
+
+    /// The peer does not speak the expected protocol or file format.
+    pub fn is_protocol(&self) -> bool {
+        matches!(
+            self,
+            Self::TcpHeaderProtocol(_)
+                | Self::TcpHeaderVersion(_)
+                | Self::TcpEndOfHeader(_)
+                | Self::BadAcmiFileType(_)
+                | Self::BadAcmiFileVersion(_)
+        )
+    }
+
+    /// Transient I/O error, after which retrying (e.g. reconnecting) may
+    /// succeed. Parse and protocol errors are never recoverable.
+    pub fn is_recoverable(&self) -> bool {
+        use std::io::ErrorKind;
+
+        let error = match self {
+            Self::TcpConnect(error)
+            | Self::TcpRead(error)
+            | Self::TcpWrite(error)
+            | Self::AcmiReaderRead(error) => error,
+            _ => return false,
+        };
+        matches!(
+            error.kind(),
+            ErrorKind::ConnectionRefused
+                | ErrorKind::ConnectionReset
+                | ErrorKind::ConnectionAborted
+                | ErrorKind::NotConnected
+                | ErrorKind::BrokenPipe
+                | ErrorKind::TimedOut
+                | ErrorKind::Interrupted
+                | ErrorKind::UnexpectedEof
+                | ErrorKind::WouldBlock
+        )
+    }
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[cfg(test)]
+mod test {
+    use std::io::{self, ErrorKind};
+
+    use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+    use super::*;
+
+    #[test]
+    fn test_classification() {
+        let io = || io::Error::from(ErrorKind::ConnectionReset);
+        for error in [
+            Error::TcpConnect(io()),
+            Error::TcpRead(io()),
+            Error::TcpWrite(io()),
+            Error::AcmiReaderRead(io()),
+        ] {
+            assert!(error.is_io() && !error.is_parse() && !error.is_protocol());
+            assert!(error.is_recoverable());
+        }
+        #[cfg(feature = "file")]
+        assert!(Error::Zip(zip::result::ZipError::Io(io())).is_io());
+        assert!(!Error::AcmiReaderRead(io::Error::from(ErrorKind::InvalidData)).is_recoverable());
+
+        for error in [
+            Error::TcpHeaderProtocol(String::new()),
+            Error::TcpHeaderVersion(String::new()),
+            Error::TcpEndOfHeader(1),
+            Error::BadAcmiFileType(String::new()),
+            Error::BadAcmiFileVersion(String::new()),
+        ] {
+            assert!(!error.is_io() && !error.is_parse() && error.is_protocol());
+            assert!(!error.is_recoverable());
+        }
+
+        #[cfg(feature = "file")]
+        assert!(Error::Zip(zip::result::ZipError::FileNotFound).is_parse());
+        for error in [
+            Error::AcmiReaderEol,
+            Error::ParseInt("x".parse::<u64>().unwrap_err()),
+            Error::ParseDateTime(OffsetDateTime::parse("x", &Rfc3339).unwrap_err()),
+            Error::ParseFloat("x".parse::<f64>().unwrap_err()),
+            Error::MalformedEvent(String::new()),
+            Error::MalformedGlobalProperty(String::new()),
+            Error::MalformedObjectProperty(String::new()),
+            Error::MalformedCoords(String::new()),
+            Error::UnexpectedLine(String::new()),
+            Error::DuplicateProperty(String::new()),
+        ] {
+            assert!(!error.is_io() && error.is_parse() && !error.is_protocol());
+            assert!(!error.is_recoverable());
+        }
+    }
+}
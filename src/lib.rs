@@ -1,16 +1,45 @@
 pub mod acmi;
+#[cfg(all(test, feature = "net"))]
+mod capture_subscriber;
+#[cfg(feature = "net")]
+pub mod client;
 pub mod error;
+#[cfg(feature = "net")]
+pub mod reconnect;
+#[cfg(feature = "net")]
 pub mod tcp;
+#[cfg(test)]
+mod temp_dir;
+#[cfg(feature = "testing")]
+pub mod testing;
 
+#[cfg(feature = "net")]
+use std::{future::Future, net::SocketAddr, pin::Pin, task::Poll};
+
+#[cfg(feature = "net")]
 use tokio::{
     io::BufStream,
     net::{TcpStream, ToSocketAddrs},
 };
 
-use crate::{acmi::RealTimeReader, error::Result};
+#[cfg(feature = "net")]
+use crate::{
+    acmi::RealTimeReader,
+    error::{Error, Result},
+};
+
+#[cfg(feature = "net")]
+pub use crate::{client::TacviewClient, reconnect::ReconnectingReader};
 
+#[cfg(feature = "net")]
 pub type TcpRealTimeReader = RealTimeReader<BufStream<TcpStream>>;
 
+#[cfg(feature = "gzip")]
+pub type GzipTcpRealTimeReader = RealTimeReader<
+    tokio::io::BufReader<async_compression::tokio::bufread::GzipDecoder<BufStream<TcpStream>>>,
+>;
+
+#[cfg(feature = "net")]
 pub async fn connect<A>(
     addr: A,
     username: &str,
@@ -22,3 +51,126 @@ where
     let tcp_stream = crate::tcp::connect(addr, username, password).await?;
     RealTimeReader::try_from_reader(tcp_stream).await
 }
+
+/// Connects, reads the header, and returns a [`TacviewClient`] ready to
+/// [`poll`](TacviewClient::poll), with the header.
+#[cfg(feature = "net")]
+pub async fn connect_world<A>(
+    addr: A,
+    username: &str,
+    password: &str,
+) -> Result<(acmi::Header, TacviewClient)>
+where
+    A: ToSocketAddrs,
+{
+    let client = TacviewClient::connect(addr, username, password).await?;
+    Ok((client.header().clone(), client))
+}
+
+/// Connects to every address concurrently and keeps the first one to complete
+/// the handshake and the header, returning it with the reader. The other
+/// attempts are cancelled. Fails with the last error if every attempt fails.
+#[cfg(feature = "net")]
+pub async fn connect_any(
+    addrs: &[SocketAddr],
+    username: &str,
+    password: &str,
+) -> Result<(SocketAddr, TcpRealTimeReader)> {
+    type Attempt<'a> =
+        Pin<Box<dyn Future<Output = Result<(SocketAddr, TcpRealTimeReader)>> + Send + 'a>>;
+
+    let mut attempts = addrs
+        .iter()
+        .map(|&addr| -> Attempt<'_> {
+            Box::pin(async move { Ok((addr, connect(addr, username, password).await?)) })
+        })
+        .collect::<Vec<_>>();
+    let mut last_error = None;
+
+    std::future::poll_fn(move |cx| {
+        let mut i = 0;
+        while i < attempts.len() {
+            match attempts[i].as_mut().poll(cx) {
+                Poll::Ready(Ok(connected)) => return Poll::Ready(Ok(connected)),
+                Poll::Ready(Err(error)) => {
+                    tracing::debug!(%error, "connection attempt failed");
+                    last_error = Some(error);
+                    drop(attempts.swap_remove(i));
+                }
+                Poll::Pending => i += 1,
+            }
+        }
+        if attempts.is_empty() {
+            Poll::Ready(Err(last_error.take().unwrap_or_else(|| {
+                Error::TcpConnect(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "no address to connect to",
+                ))
+            })))
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+/// Same as [`connect`], for relays compressing the stream with gzip. The
+/// handshake is plaintext, only the ACMI body after it is decompressed.
+#[cfg(feature = "gzip")]
+pub async fn connect_gzip<A>(
+    addr: A,
+    username: &str,
+    password: &str,
+) -> Result<GzipTcpRealTimeReader>
+where
+    A: ToSocketAddrs,
+{
+    let tcp_stream = crate::tcp::connect(addr, username, password).await?;
+    let decoder = async_compression::tokio::bufread::GzipDecoder::new(tcp_stream);
+    RealTimeReader::try_from_reader(tokio::io::BufReader::new(decoder)).await
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_connect_any() {
+        use crate::{acmi::record::Record, testing::MockServer};
+
+        use super::*;
+
+        let dead = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let dead_addr = dead.local_addr().unwrap();
+        drop(dead);
+
+        let server = MockServer::bind(["#1"]).await.unwrap();
+        let live_addr = server.local_addr().unwrap();
+        let addrs = [dead_addr, live_addr];
+        let (login, connected) = tokio::join!(server.serve_one(), connect_any(&addrs, "pilot", ""));
+        assert!(login.is_ok());
+        let (addr, mut reader) = connected.unwrap();
+        assert_eq!(addr, live_addr);
+        assert_eq!(reader.next().await.unwrap(), Some(Record::Frame(1.0)));
+
+        assert!(connect_any(&[dead_addr], "pilot", "").await.is_err());
+        assert!(connect_any(&[], "pilot", "").await.is_err());
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_connect_world() {
+        use crate::testing::MockServer;
+
+        use super::*;
+
+        let server = MockServer::bind(["#0", "A,T=1|2|3", "#1"]).await.unwrap();
+        let addr = server.local_addr().unwrap();
+        let (login, connected) = tokio::join!(server.serve_one(), connect_world(addr, "pilot", ""));
+        login.unwrap();
+        let (header, mut client) = connected.unwrap();
+        assert_eq!(header.file_version, "2.2");
+        assert_eq!(client.world().header(), Some(&header));
+        client.poll().await.unwrap();
+        assert_eq!(client.objects().count(), 1);
+    }
+}
@@ -0,0 +1,398 @@
+use std::{collections::VecDeque, time::Duration};
+
+use time::OffsetDateTime;
+use tokio::net::ToSocketAddrs;
+
+use crate::{
+    acmi::{
+        record::{global_property::GlobalProperty, Record},
+        Header,
+    },
+    error::{Error, Result},
+    TcpRealTimeReader,
+};
+
+/// Item yielded by [`ReconnectingReader::next`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item {
+    Record(Record),
+    /// The connection was lost and established again: records sent in
+    /// between are missing, so the state may have a gap.
+    Resumed,
+    /// Same as [`Resumed`](Self::Resumed), but the server is now streaming
+    /// another mission, see
+    /// [`with_require_same_mission`](ReconnectingReader::with_require_same_mission).
+    /// Consumers should reset their state.
+    Reset,
+}
+
+/// Real-time telemetry reader connecting again, after an exponential backoff,
+/// when the connection is lost.
+///
+/// The connection is considered lost on a
+/// [recoverable](Error::is_recoverable) error, and when the stream ends if
+/// [`with_reconnect_on_end`](Self::with_reconnect_on_end) is set. Parse and
+/// protocol errors are returned as is.
+#[derive(Debug)]
+pub struct ReconnectingReader<A> {
+    addr: A,
+    username: String,
+    password: String,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    max_retries: Option<usize>,
+    reconnect_on_end: bool,
+    require_same_mission: bool,
+    reader: TcpRealTimeReader,
+    /// `RecordingTime` and `Title` of the mission being streamed.
+    mission: (Option<OffsetDateTime>, Option<String>),
+    /// Records read after reconnecting up to the first frame, sent after the
+    /// signal.
+    buffered: VecDeque<Record>,
+}
+
+impl<A> ReconnectingReader<A>
+where
+    A: ToSocketAddrs + Clone,
+{
+    /// Connects with [`connect`](crate::connect). The first connection is not
+    /// retried.
+    pub async fn connect(addr: A, username: &str, password: &str) -> Result<Self> {
+        let reader = crate::connect(addr.clone(), username, password).await?;
+        Ok(Self {
+            addr,
+            username: username.to_string(),
+            password: password.to_string(),
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            max_retries: None,
+            reconnect_on_end: false,
+            require_same_mission: false,
+            reader,
+            mission: (None, None),
+            buffered: VecDeque::new(),
+        })
+    }
+
+    /// Waits `initial` before the first attempt to connect again, doubling
+    /// the delay after each failed attempt up to `max`. 500 ms and 30 s by
+    /// default.
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Gives up after `max_retries` failed attempts in a row, returning the
+    /// last error. Retries forever by default.
+    pub fn with_max_retries(mut self, max_retries: Option<usize>) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Connects again when the server closes the stream cleanly, e.g. when a
+    /// mission ends and the next one is about to start, instead of returning
+    /// `None`. Disabled by default.
+    pub fn with_reconnect_on_end(mut self, reconnect_on_end: bool) -> Self {
+        self.reconnect_on_end = reconnect_on_end;
+        self
+    }
+
+    /// Signals [`Item::Reset`] instead of [`Item::Resumed`] when the
+    /// `RecordingTime` or `Title` sent after reconnecting, before the first
+    /// frame, differ from the ones of the mission streamed before, so two
+    /// missions are not blended. A mission which sent neither is never
+    /// considered the same.
+    pub fn with_require_same_mission(mut self, require_same_mission: bool) -> Self {
+        self.require_same_mission = require_same_mission;
+        self
+    }
+
+    /// Header of the current connection.
+    pub fn header(&self) -> &Header {
+        &self.reader.header
+    }
+
+    /// Reads the next record, connecting again first if the connection was
+    /// lost. Returns `None` when the stream ends, unless
+    /// [`with_reconnect_on_end`](Self::with_reconnect_on_end) is set and the
+    /// retries are exhausted.
+    pub async fn next(&mut self) -> Result<Option<Item>> {
+        if let Some(record) = self.buffered.pop_front() {
+            return Ok(Some(Item::Record(record)));
+        }
+
+        let error = match self.reader.next().await {
+            Ok(Some(record)) => {
+                self.update_mission(&record);
+                return Ok(Some(Item::Record(record)));
+            }
+            Ok(None) if !self.reconnect_on_end => return Ok(None),
+            Ok(None) => None,
+            Err(error) if error.is_recoverable() => Some(error),
+            Err(error) => return Err(error),
+        };
+        match &error {
+            Some(error) => tracing::warn!(%error, "connection lost, reconnecting"),
+            None => tracing::warn!("stream ended, reconnecting"),
+        }
+        if !self.reconnect(error).await? {
+            return Ok(None);
+        }
+        self.resume().await
+    }
+
+    /// Buffers the records of the new connection up to the first frame, to
+    /// compare the mission they describe with the previous one.
+    async fn resume(&mut self) -> Result<Option<Item>> {
+        let previous = std::mem::take(&mut self.mission);
+        loop {
+            match self.reader.next().await {
+                Ok(Some(record)) => {
+                    self.update_mission(&record);
+                    let is_frame = matches!(record, Record::Frame(_));
+                    self.buffered.push_back(record);
+                    if is_frame {
+                        break;
+                    }
+                }
+                // the next call notices the connection is lost again
+                Ok(None) => break,
+                Err(error) if error.is_recoverable() => break,
+                Err(error) => return Err(error),
+            }
+        }
+
+        let same_mission = previous != (None, None) && previous == self.mission;
+        if self.require_same_mission && !same_mission {
+            Ok(Some(Item::Reset))
+        } else {
+            Ok(Some(Item::Resumed))
+        }
+    }
+
+    /// Returns whether a new connection was established, or `false` if the
+    /// retries are exhausted without error to return.
+    async fn reconnect(&mut self, mut error: Option<Error>) -> Result<bool> {
+        let mut backoff = self.initial_backoff;
+        for attempt in 0.. {
+            if self
+                .max_retries
+                .is_some_and(|max_retries| attempt >= max_retries)
+            {
+                break;
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(self.max_backoff);
+            match crate::connect(self.addr.clone(), &self.username, &self.password).await {
+                Ok(reader) => {
+                    tracing::info!(attempt, "reconnected");
+                    self.reader = reader;
+                    return Ok(true);
+                }
+                Err(new_error) if new_error.is_recoverable() => {
+                    tracing::debug!(attempt, error = %new_error, "reconnection attempt failed");
+                    error = Some(new_error);
+                }
+                Err(new_error) => return Err(new_error),
+            }
+        }
+        error.map_or(Ok(false), Err)
+    }
+
+    fn update_mission(&mut self, record: &Record) {
+        let (recording_time, title) = mission_of(record);
+        if recording_time.is_some() {
+            self.mission.0 = recording_time;
+        }
+        if title.is_some() {
+            self.mission.1 = title;
+        }
+    }
+}
+
+/// `RecordingTime` and `Title` set by a record.
+fn mission_of(record: &Record) -> (Option<OffsetDateTime>, Option<String>) {
+    let mut mission = (None, None);
+    if let Record::GlobalProperties(global_properties) = record {
+        for global_property in global_properties {
+            match global_property {
+                GlobalProperty::RecordingTime(recording_time) => {
+                    mission.0 = Some(*recording_time);
+                }
+                GlobalProperty::Title(title) => mission.1 = Some(title.clone()),
+                _ => {}
+            }
+        }
+    }
+    mission
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_reconnect() {
+        use crate::testing::MockServer;
+
+        use super::*;
+
+        // the mission is described over several lines
+        let recording_time = "0,RecordingTime=2016-02-18T16:44:12Z";
+        let mut server = MockServer::bind([recording_time, "0,Title=A", "#0", "A,T=1|2|3"])
+            .await
+            .unwrap();
+        let addr = server.local_addr().unwrap();
+
+        let (served, items) = tokio::join!(
+            async {
+                server.serve_one().await?;
+                server.serve_one().await?;
+                server.set_script([recording_time, "0,Title=B", "#0"]);
+                server.serve_one().await
+            },
+            async {
+                let mut reader = ReconnectingReader::connect(addr, "pilot", "")
+                    .await
+                    .unwrap()
+                    .with_backoff(Duration::from_millis(1), Duration::from_millis(10))
+                    .with_reconnect_on_end(true)
+                    .with_require_same_mission(true);
+                let mut items = Vec::new();
+                for _ in 0..13 {
+                    items.push(reader.next().await.unwrap().unwrap());
+                }
+                items
+            }
+        );
+        served.unwrap();
+
+        let record = |line: &str| Item::Record(line.parse().unwrap());
+        assert_eq!(
+            items,
+            [
+                record(recording_time),
+                record("0,Title=A"),
+                record("#0"),
+                record("A,T=1|2|3"),
+                Item::Resumed,
+                record(recording_time),
+                record("0,Title=A"),
+                record("#0"),
+                record("A,T=1|2|3"),
+                Item::Reset,
+                record(recording_time),
+                record("0,Title=B"),
+                record("#0"),
+            ]
+        );
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_reconnect_after_reset() {
+        use crate::testing::MockServer;
+
+        use super::*;
+
+        let mut server = MockServer::bind(["#0", "A,T=1|2|3", "#1"]).await.unwrap();
+        server.set_reset_after(Some(2));
+        let addr = server.local_addr().unwrap();
+
+        let (served, items) = tokio::join!(
+            async {
+                server.serve_one().await?;
+                server.set_reset_after(None);
+                // closing before the banner is retried too
+                server.reject_one().await?;
+                server.serve_one().await
+            },
+            async {
+                // reconnecting on errors is enabled without reconnect_on_end
+                let mut reader = ReconnectingReader::connect(addr, "pilot", "")
+                    .await
+                    .unwrap()
+                    .with_backoff(Duration::from_millis(1), Duration::from_millis(10));
+                let mut items = Vec::new();
+                while let Some(item) = reader.next().await.unwrap() {
+                    items.push(item);
+                }
+                items
+            }
+        );
+        served.unwrap();
+
+        let record = |line: &str| Item::Record(line.parse().unwrap());
+        assert_eq!(
+            items,
+            [
+                record("#0"),
+                record("A,T=1|2|3"),
+                Item::Resumed,
+                record("#0"),
+                record("A,T=1|2|3"),
+                record("#1"),
+            ]
+        );
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_reconnect_unknown_mission() {
+        use crate::testing::MockServer;
+
+        use super::*;
+
+        let server = MockServer::bind(["#0"]).await.unwrap();
+        let addr = server.local_addr().unwrap();
+
+        let (served, items) = tokio::join!(
+            async {
+                server.serve_one().await?;
+                server.serve_one().await
+            },
+            async {
+                let mut reader = ReconnectingReader::connect(addr, "pilot", "")
+                    .await
+                    .unwrap()
+                    .with_backoff(Duration::from_millis(1), Duration::from_millis(10))
+                    .with_reconnect_on_end(true)
+                    .with_require_same_mission(true);
+                let mut items = Vec::new();
+                for _ in 0..3 {
+                    items.push(reader.next().await.unwrap().unwrap());
+                }
+                items
+            }
+        );
+        served.unwrap();
+
+        let frame = Item::Record(Record::Frame(0.0));
+        assert_eq!(items, [frame.clone(), Item::Reset, frame]);
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_end_of_stream() {
+        use crate::testing::MockServer;
+
+        use super::*;
+
+        let server = MockServer::bind(["#0"]).await.unwrap();
+        let addr = server.local_addr().unwrap();
+
+        let (served, items) = tokio::join!(server.serve_one(), async {
+            let mut reader = ReconnectingReader::connect(addr, "pilot", "")
+                .await
+                .unwrap();
+            let mut items = Vec::new();
+            while let Some(item) = reader.next().await.unwrap() {
+                items.push(item);
+            }
+            items
+        });
+        served.unwrap();
+
+        assert_eq!(items, [Item::Record(Record::Frame(0.0))]);
+    }
+}
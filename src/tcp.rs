@@ -1,23 +1,45 @@
+use std::{borrow::Cow, future::Future};
+
 use crc::{Crc, CRC_32_ISO_HDLC};
 use tokio::{
-    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufStream},
+    io::{
+        AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt,
+        BufStream,
+    },
     net::{TcpStream, ToSocketAddrs},
 };
 
-use crate::error::{Error, Result};
+use crate::{
+    acmi::{parse_header, validate_header, Header},
+    error::{Error, Result},
+};
 
-fn hash_password(password: &str) -> String {
+/// Hashes a password the way Tacview expects it in the handshake:
+///
+/// 1. encode the password in UTF-16 little endian, without BOM nor terminator
+///    (see [`encode_password`]),
+/// 2. compute the CRC-32 (ISO-HDLC, as used by zlib) of those bytes,
+/// 3. format the checksum in lowercase hexadecimal, without leading zeros.
+///
+/// An empty password hashes to `0`.
+pub fn hash_password(password: &str) -> String {
     const CRC: Crc<u32> = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+    let checksum = CRC.checksum(&encode_password(password));
+    format!("{checksum:x}")
+}
+
+/// UTF-16 little endian bytes of the password, the input of the checksum in
+/// [`hash_password`].
+pub fn encode_password(password: &str) -> Vec<u8> {
     let password_utf16 = password.encode_utf16();
     let mut password_bytes = Vec::<u8>::with_capacity(password.len() * 2);
     for c in password_utf16 {
-        password_bytes.push((c >> 0) as u8);
-        password_bytes.push((c >> 8) as u8);
+        password_bytes.extend_from_slice(&c.to_le_bytes());
     }
-    let checksum = CRC.checksum(&password_bytes);
-    format!("{checksum:x}")
+    password_bytes
 }
 
+#[tracing::instrument(level = "debug", skip_all, fields(username = %username))]
 pub async fn connect<A>(addr: A, username: &str, password: &str) -> Result<BufStream<TcpStream>>
 where
     A: ToSocketAddrs,
@@ -27,63 +49,353 @@ where
     from_tcp_stream(tcp_stream, username, password).await
 }
 
-pub async fn from_tcp_stream(
-    mut tcp_stream: BufStream<TcpStream>,
+/// Connects and reads the ACMI header, returning the stream positioned at the
+/// first record. Useful to own the transport, e.g. to set socket options,
+/// while still building a [`RealTimeReader`](crate::acmi::RealTimeReader)
+/// with [`RealTimeReader::new`](crate::acmi::RealTimeReader::new).
+pub async fn connect_raw<A>(
+    addr: A,
     username: &str,
     password: &str,
-) -> Result<BufStream<TcpStream>> {
+) -> Result<(BufStream<TcpStream>, Header)>
+where
+    A: ToSocketAddrs,
+{
+    let mut tcp_stream = connect(addr, username, password).await?;
+    let header = parse_header(&mut tcp_stream, validate_header).await?;
+    Ok((tcp_stream, header))
+}
+
+/// Authentication step run right after the connection is established,
+/// before the ACMI header. [`TacviewHandshake`] implements the Tacview
+/// protocol; forks of it can plug their own scheme with
+/// [`from_stream_with_handshake`] and still reuse the ACMI parsing.
+pub trait Handshake {
+    fn handshake<'a, S>(&'a self, stream: &'a mut S) -> impl Future<Output = Result<()>> + 'a
+    where
+        S: AsyncBufRead + AsyncWrite + Unpin;
+}
+
+/// The Tacview real-time telemetry handshake: the server banner followed by
+/// the username and the [hashed](hash_password) password.
+#[derive(Debug, Clone)]
+pub struct TacviewHandshake<'a> {
+    pub username: &'a str,
+    pub password: &'a str,
+}
+
+impl Handshake for TacviewHandshake<'_> {
+    async fn handshake<S>(&self, stream: &mut S) -> Result<()>
+    where
+        S: AsyncBufRead + AsyncWrite + Unpin,
+    {
+        handshake(stream, self.username, Password::Plain(self.password)).await
+    }
+}
+
+/// Password sent in the handshake, hashed with [`hash_password`] unless it
+/// already is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Password<'a> {
+    Plain(&'a str),
+    /// Sent as is, e.g. to experiment with nonstandard servers.
+    Hashed(&'a str),
+}
+
+impl<'a> Password<'a> {
+    /// Hash written in the handshake.
+    pub fn hash(&self) -> Cow<'a, str> {
+        match self {
+            Self::Plain(password) => Cow::Owned(hash_password(password)),
+            Self::Hashed(hash) => Cow::Borrowed(hash),
+        }
+    }
+}
+
+/// The Tacview handshake: [`read_banner`] then [`write_login`].
+pub async fn handshake<S>(stream: &mut S, username: &str, password: Password<'_>) -> Result<()>
+where
+    S: AsyncBufRead + AsyncWrite + Unpin,
+{
+    read_banner(stream).await?;
+    write_login(stream, username, &password.hash()).await
+}
+
+/// Reads and checks the banner the server sends first, up to its terminating
+/// `\0`.
+pub async fn read_banner<S>(stream: &mut S) -> Result<()>
+where
+    S: AsyncBufRead + Unpin,
+{
     let mut buf = String::new();
 
     // protocol header
-    tcp_stream
-        .read_line(&mut buf)
-        .await
-        .map_err(Error::TcpRead)?;
+    read_banner_line(stream, &mut buf).await?;
     if buf != "XtraLib.Stream.0\n" {
         return Err(Error::TcpHeaderProtocol(buf));
     }
     buf.clear();
 
     // version header
-    tcp_stream
-        .read_line(&mut buf)
-        .await
-        .map_err(Error::TcpRead)?;
+    read_banner_line(stream, &mut buf).await?;
     if buf != "Tacview.RealTimeTelemetry.0\n" {
         return Err(Error::TcpHeaderVersion(buf));
     }
     buf.clear();
 
     // hostname
-    tcp_stream
-        .read_line(&mut buf)
-        .await
-        .map_err(Error::TcpRead)?;
+    read_banner_line(stream, &mut buf).await?;
     tracing::debug!(hostname = %buf, "server hostname");
 
-    let eoh = tcp_stream.read_u8().await.map_err(Error::TcpRead)?;
+    let eoh = stream.read_u8().await.map_err(Error::TcpRead)?;
     if eoh != 0 {
         return Err(Error::TcpEndOfHeader(eoh));
     }
 
-    tcp_stream
-        .write_all(b"XtraLib.Stream.0\n")
-        .await
-        .map_err(Error::TcpWrite)?;
-    tcp_stream
-        .write_all(b"Tacview.RealTimeTelemetry.0\n")
-        .await
-        .map_err(Error::TcpWrite)?;
-    tcp_stream
-        .write_all(format!("{username}\n").as_bytes())
-        .await
-        .map_err(Error::TcpWrite)?;
-    tcp_stream
-        .write_all(format!("{}\x00", hash_password(password)).as_bytes())
+    Ok(())
+}
+
+/// Reads a line of the banner. The server closing the connection before
+/// sending it, e.g. while starting up, is a recoverable
+/// [`UnexpectedEof`](std::io::ErrorKind::UnexpectedEof) read error rather
+/// than a protocol error.
+async fn read_banner_line<S>(stream: &mut S, buf: &mut String) -> Result<()>
+where
+    S: AsyncBufRead + Unpin,
+{
+    if stream.read_line(buf).await.map_err(Error::TcpRead)? == 0 {
+        return Err(Error::TcpRead(std::io::ErrorKind::UnexpectedEof.into()));
+    }
+    Ok(())
+}
+
+/// Exact bytes written by [`write_login`]: the client headers, the username
+/// and the already hashed password, terminated by `\0`.
+pub fn login_bytes(username: &str, password_hash: &str) -> Vec<u8> {
+    format!("XtraLib.Stream.0\nTacview.RealTimeTelemetry.0\n{username}\n{password_hash}\0")
+        .into_bytes()
+}
+
+/// Writes the client headers, the username and the already hashed password,
+/// then flushes. See [`login_bytes`].
+pub async fn write_login<S>(stream: &mut S, username: &str, password_hash: &str) -> Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    stream
+        .write_all(&login_bytes(username, password_hash))
         .await
         .map_err(Error::TcpWrite)?;
+    stream.flush().await.map_err(Error::TcpWrite)
+}
+
+pub async fn from_tcp_stream(
+    tcp_stream: BufStream<TcpStream>,
+    username: &str,
+    password: &str,
+) -> Result<BufStream<TcpStream>> {
+    run_handshake(tcp_stream, &TacviewHandshake { username, password }).await
+}
+
+/// Same as [`from_tcp_stream`], with a custom [`Handshake`].
+pub async fn from_tcp_stream_with_handshake<H>(
+    tcp_stream: BufStream<TcpStream>,
+    handshake: &H,
+) -> Result<BufStream<TcpStream>>
+where
+    H: Handshake,
+{
+    run_handshake(tcp_stream, handshake).await
+}
+
+/// Runs the Tacview handshake over any stream, e.g. a TLS stream, a Unix
+/// socket or an in-memory [`tokio::io::duplex`], and returns it buffered and
+/// positioned at the ACMI header.
+pub async fn from_stream<S>(stream: S, username: &str, password: &str) -> Result<BufStream<S>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    from_stream_with_handshake(stream, &TacviewHandshake { username, password }).await
+}
+
+/// Same as [`from_stream`], with a custom [`Handshake`].
+pub async fn from_stream_with_handshake<S, H>(stream: S, handshake: &H) -> Result<BufStream<S>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    H: Handshake,
+{
+    run_handshake(BufStream::new(stream), handshake).await
+}
+
+#[tracing::instrument(name = "handshake", level = "debug", skip_all)]
+async fn run_handshake<S, H>(mut stream: S, handshake: &H) -> Result<S>
+where
+    S: AsyncBufRead + AsyncWrite + Unpin,
+    H: Handshake,
+{
+    handshake.handshake(&mut stream).await?;
+    Ok(stream)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// `(password, UTF-16LE bytes in hex, hash)`, checked against zlib's
+    /// `crc32`.
+    const VECTORS: &[(&str, &str, &str)] = &[
+        ("", "", "0"),
+        ("password", "700061007300730077006f0072006400", "f335183e"),
+        ("tacview", "7400610063007600690065007700", "821df408"),
+        (
+            "p\u{e4}ssw\u{f6}rd",
+            "7000e400730073007700f60072006400",
+            "ce8cde3a",
+        ),
+        ("\u{1f6e9}", "3dd8e9de", "4e482c9e"),
+    ];
+
+    #[test]
+    fn test_hash_password() {
+        for (password, bytes, hash) in VECTORS {
+            let encoded: String = encode_password(password)
+                .iter()
+                .map(|b| format!("{b:02x}"))
+                .collect();
+            assert_eq!(&encoded, bytes, "{password}");
+            assert_eq!(&hash_password(password), hash, "{password}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_from_stream() {
+        let (client, server) = tokio::io::duplex(256);
+        let server = async {
+            let mut server = BufStream::new(server);
+            server
+                .write_all(b"XtraLib.Stream.0\nTacview.RealTimeTelemetry.0\nhost\n\0")
+                .await
+                .unwrap();
+            server.flush().await.unwrap();
+            let mut login = Vec::new();
+            for _ in 0..3 {
+                server.read_until(b'\n', &mut login).await.unwrap();
+            }
+            server.read_until(0, &mut login).await.unwrap();
+            login
+        };
+        let (login, stream) = tokio::join!(server, from_stream(client, "pilot", "password"));
+        assert!(stream.is_ok());
+        assert_eq!(login, login_bytes("pilot", "f335183e"));
+
+        let (client, mut server) = tokio::io::duplex(256);
+        server.write_all(b"Other.Stream.0\n").await.unwrap();
+        assert!(matches!(
+            from_stream(client, "pilot", "").await,
+            Err(Error::TcpHeaderProtocol(_))
+        ));
+
+        // closed before the banner
+        let (client, server) = tokio::io::duplex(256);
+        drop(server);
+        let error = from_stream(client, "pilot", "").await.unwrap_err();
+        assert!(matches!(error, Error::TcpRead(_)));
+        assert!(error.is_recoverable());
+    }
+
+    #[test]
+    fn test_connect_is_send() {
+        fn assert_send<T: Send>(_: &T) {}
+        let future = connect("localhost:42674", "pilot", "");
+        assert_send(&future);
+    }
+
+    #[test]
+    fn test_login_bytes() {
+        assert_eq!(
+            login_bytes("pilot", &Password::Plain("password").hash()),
+            b"XtraLib.Stream.0\nTacview.RealTimeTelemetry.0\npilot\nf335183e\0"
+        );
+        assert_eq!(
+            login_bytes("pilot", &Password::Hashed("F335183E").hash()),
+            b"XtraLib.Stream.0\nTacview.RealTimeTelemetry.0\npilot\nF335183E\0"
+        );
+        assert_eq!(
+            login_bytes("", &Password::Plain("").hash()),
+            b"XtraLib.Stream.0\nTacview.RealTimeTelemetry.0\n\n0\0"
+        );
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_connect_raw() {
+        use crate::{
+            acmi::{record::Record, RealTimeReader},
+            testing::MockServer,
+        };
+
+        let server = MockServer::bind(["#1"]).await.unwrap();
+        let addr = server.local_addr().unwrap();
+        let (login, record) = tokio::join!(server.serve_one(), async {
+            let (stream, header) = connect_raw(addr, "pilot", "").await.unwrap();
+            assert_eq!(header.file_version, "2.2");
+            RealTimeReader::new(stream, header).next().await.unwrap()
+        });
+        assert_eq!(login.unwrap().password_hash, "0");
+        assert_eq!(record, Some(Record::Frame(1.0)));
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_tracing_spans() {
+        use crate::{capture_subscriber::CaptureSubscriber, testing::MockServer};
+
+        let capture = CaptureSubscriber::default();
+        let _guard = tracing::subscriber::set_default(capture.clone());
 
-    tcp_stream.flush().await.map_err(Error::TcpWrite)?;
+        let server = MockServer::bind(["#1"]).await.unwrap();
+        let addr = server.local_addr().unwrap();
+        let (login, stream) = tokio::join!(server.serve_one(), connect(addr, "pilot", ""));
+        login.unwrap();
+        stream.unwrap();
 
-    Ok(tcp_stream)
+        let fields = capture.fields();
+        let position = |expected: &str| {
+            fields
+                .iter()
+                .position(|f| f == expected)
+                .unwrap_or_else(|| panic!("{expected} in {fields:?}"))
+        };
+        assert_eq!(position("username=pilot"), position("span=connect") + 1);
+        assert!(position("span=handshake") > position("span=connect"));
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_custom_handshake() {
+        use crate::testing::MockServer;
+
+        struct UppercaseHandshake;
+
+        impl Handshake for UppercaseHandshake {
+            async fn handshake<S>(&self, stream: &mut S) -> Result<()>
+            where
+                S: AsyncBufRead + AsyncWrite + Unpin,
+            {
+                read_banner(stream).await?;
+                write_login(stream, "fork", &hash_password("secret").to_uppercase()).await
+            }
+        }
+
+        let server = MockServer::bind(["#1"]).await.unwrap();
+        let addr = server.local_addr().unwrap();
+        let (login, stream) = tokio::join!(server.serve_one(), async {
+            let tcp_stream = BufStream::new(TcpStream::connect(addr).await.unwrap());
+            from_tcp_stream_with_handshake(tcp_stream, &UppercaseHandshake).await
+        });
+        let login = login.unwrap();
+        assert!(stream.is_ok());
+        assert_eq!(login.username, "fork");
+        assert_eq!(login.password_hash, hash_password("secret").to_uppercase());
+    }
 }
@@ -0,0 +1,226 @@
+//! Helpers to test consumers against deterministic input, without a real
+//! Tacview server.
+
+use std::{io, net::SocketAddr, time::Duration};
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufStream},
+    net::{TcpListener, TcpStream},
+};
+
+/// Credentials sent by a client during the handshake.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockLogin {
+    pub username: String,
+    /// Hashed password, as computed by [`hash_password`](crate::tcp::hash_password).
+    pub password_hash: String,
+}
+
+/// Fake real-time telemetry server listening on localhost. Each accepted
+/// client goes through the handshake, then receives the ACMI header and the
+/// script lines, and is disconnected.
+#[derive(Debug)]
+pub struct MockServer {
+    listener: TcpListener,
+    script: Vec<String>,
+    gzip: bool,
+    reset_after: Option<usize>,
+}
+
+impl MockServer {
+    /// Binds to a free localhost port. The script lines are sent without the
+    /// `FileType` and `FileVersion` header, which the server sends itself.
+    pub async fn bind<I>(script: I) -> io::Result<Self>
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        Ok(Self {
+            listener,
+            script: script.into_iter().map(Into::into).collect(),
+            gzip: false,
+            reset_after: None,
+        })
+    }
+
+    /// Compresses everything sent after the handshake with gzip, like some
+    /// relays do.
+    #[cfg(feature = "gzip")]
+    pub fn gzip(mut self) -> Self {
+        self.gzip = true;
+        self
+    }
+
+    /// Replaces the script sent to the next clients.
+    pub fn set_script<I>(&mut self, script: I)
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.script = script.into_iter().map(Into::into).collect();
+    }
+
+    /// Resets the connection of the next clients after sending the first
+    /// `lines` script lines, like a crashing server, instead of closing it
+    /// cleanly after the whole script. `None` to send the whole script again.
+    pub fn set_reset_after(&mut self, lines: Option<usize>) {
+        self.reset_after = lines;
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Accepts a single client and closes the connection before the
+    /// handshake, like a server which is still starting up.
+    pub async fn reject_one(&self) -> io::Result<()> {
+        let (stream, _) = self.listener.accept().await?;
+        drop(stream);
+        Ok(())
+    }
+
+    /// Accepts and serves a single client, returning the credentials it sent.
+    /// Should be polled concurrently with the client, e.g. with
+    /// `tokio::join!`.
+    pub async fn serve_one(&self) -> io::Result<MockLogin> {
+        let (stream, _) = self.listener.accept().await?;
+        let mut stream = BufStream::new(stream);
+
+        stream
+            .write_all(b"XtraLib.Stream.0\nTacview.RealTimeTelemetry.0\nMockServer\n\x00")
+            .await?;
+        stream.flush().await?;
+
+        let mut buf = String::new();
+        for _ in 0..2 {
+            // protocol and version headers
+            stream.read_line(&mut buf).await?;
+        }
+        buf.clear();
+        stream.read_line(&mut buf).await?;
+        let username = buf.trim_end_matches('\n').to_string();
+        let mut password_hash = Vec::new();
+        stream.read_until(0, &mut password_hash).await?;
+        password_hash.pop();
+        let password_hash = String::from_utf8_lossy(&password_hash).into_owned();
+
+        let mut body = b"FileType=text/acmi/tacview\nFileVersion=2.2\n".to_vec();
+        let lines = self.reset_after.unwrap_or(self.script.len());
+        for line in self.script.iter().take(lines) {
+            body.extend_from_slice(line.as_bytes());
+            body.push(b'\n');
+        }
+        if self.gzip {
+            #[cfg(feature = "gzip")]
+            {
+                let mut encoder = async_compression::tokio::write::GzipEncoder::new(stream);
+                encoder.write_all(&body).await?;
+                if self.reset_after.is_some() {
+                    encoder.flush().await?;
+                    reset(encoder.get_ref().get_ref())?;
+                } else {
+                    encoder.shutdown().await?;
+                }
+            }
+        } else {
+            stream.write_all(&body).await?;
+            if self.reset_after.is_some() {
+                stream.flush().await?;
+                reset(stream.get_ref())?;
+            } else {
+                stream.shutdown().await?;
+            }
+        }
+
+        Ok(MockLogin {
+            username,
+            password_hash,
+        })
+    }
+}
+
+/// Makes the stream send a TCP reset instead of a clean close once dropped.
+#[allow(deprecated)]
+fn reset(stream: &TcpStream) -> io::Result<()> {
+    // a zero linger never blocks on drop
+    stream.set_linger(Some(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::acmi::record::Record;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_mock_server() {
+        let server = MockServer::bind(["#0", "A,T=1|2|3", "#1.5"]).await.unwrap();
+        let addr = server.local_addr().unwrap();
+
+        let (login, records) = tokio::join!(server.serve_one(), async {
+            let mut reader = crate::connect(addr, "pilot", "password").await.unwrap();
+            let mut records = Vec::new();
+            while let Some(record) = reader.next().await.unwrap() {
+                records.push(record);
+            }
+            records
+        });
+
+        assert_eq!(
+            login.unwrap(),
+            MockLogin {
+                username: "pilot".to_string(),
+                password_hash: "f335183e".to_string(),
+            }
+        );
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[2], Record::Frame(1.5));
+    }
+
+    #[tokio::test]
+    async fn test_for_each() {
+        let script = ["#0", "A,T=1|2|3", "#1", "-A"];
+        let server = MockServer::bind(script).await.unwrap();
+        let addr = server.local_addr().unwrap();
+        let mut count = 0;
+        let (login, result) = tokio::join!(server.serve_one(), async {
+            let reader = crate::connect(addr, "pilot", "").await.unwrap();
+            reader.for_each(|_| count += 1).await
+        });
+        login.unwrap();
+        result.unwrap();
+        assert_eq!(count, 4);
+
+        let (login, result) = tokio::join!(server.serve_one(), async {
+            let reader = crate::connect(addr, "pilot", "").await.unwrap();
+            reader
+                .try_for_each(|record| match record {
+                    Record::Remove(_) => Err(crate::error::Error::AcmiReaderEol),
+                    _ => Ok(()),
+                })
+                .await
+        });
+        login.unwrap();
+        assert!(matches!(result, Err(crate::error::Error::AcmiReaderEol)));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[tokio::test]
+    async fn test_gzip() {
+        let server = MockServer::bind(["#0", "A,T=1|2|3"]).await.unwrap().gzip();
+        let addr = server.local_addr().unwrap();
+
+        let (login, records) = tokio::join!(server.serve_one(), async {
+            let mut reader = crate::connect_gzip(addr, "pilot", "").await.unwrap();
+            let mut records = Vec::new();
+            while let Some(record) = reader.next().await.unwrap() {
+                records.push(record.to_string());
+            }
+            records
+        });
+
+        login.unwrap();
+        assert_eq!(records, vec!["#0", "A,T=1|2|3"]);
+    }
+}